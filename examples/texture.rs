@@ -12,13 +12,14 @@ fn label_to_color(label: usize) -> [u8; 3] {
     }
 }
 
-fn save_frame(mrf: &MRF<usize>, width: usize, height: usize, path: &str) {
+fn save_frame(mrf: &MRF<usize>, path: &str) {
+    let layout = mrf.layout().and_then(Layout::as_grid).unwrap();
+    let (width, height) = layout.shape();
     let mut img = RgbImage::new(width as u32, height as u32);
     for i in 0..mrf.num_nodes() {
-        let x = (i % width) as u32;
-        let y = (i / width) as u32;
+        let (x, y) = layout.coords(i);
         let color = label_to_color(*mrf.graph().get_node(i).state());
-        img.put_pixel(x, y, image::Rgb(color));
+        img.put_pixel(x as u32, y as u32, image::Rgb(color));
     }
     img.save(path).unwrap();
 }
@@ -29,21 +30,13 @@ fn main() {
     let sweeps = 100;
     let num_labels = 4;
 
-    // Build grid graph
-    let mut graph: Graph<usize> = Graph::new(width * height);
-    for r in 0..height {
-        for c in 0..width {
-            let i = r * width + c;
-            if c + 1 < width { graph.add_edge(i, i + 1); }
-            if r + 1 < height { graph.add_edge(i, i + width); }
-        }
-    }
-    graph.detect_cliques();
+    // Build grid graph; wrapping both axes makes the texture tile seamlessly
+    let mut grid: Grid2D<usize> = Grid2D::with_boundary(width, height, Four, Boundary::Toroidal);
 
     // Randomize initial state
     let mut rng = rand::rngs::StdRng::seed_from_u64(42);
-    for i in 0..graph.num_nodes() {
-        graph.get_node_mut(i).set_state(rng.random_range(0..num_labels));
+    for i in 0..grid.num_nodes() {
+        grid.graph_mut().get_node_mut(i).set_state(rng.random_range(0..num_labels));
     }
 
     let pairwise = TablePotential::pairwise(&[
@@ -55,7 +48,7 @@ fn main() {
     ]).unwrap();
 
     let mut mrf = MRF::<usize>::builder()
-        .graph(graph)
+        .grid(grid)
         .potential(pairwise)
         .build()
         .unwrap();
@@ -66,13 +59,20 @@ fn main() {
 
     std::fs::create_dir_all("frames").unwrap();
 
-    save_frame(&mrf, width, height, "frames/frame_000.png");
+    save_frame(&mrf, "frames/frame_000.png");
 
     let opts = RunOptions {
         seed: Some(42),
     };
 
-    sampler.run(&mut mrf, &proposal, opts).unwrap();
+    let report = sampler.run(&mut mrf, &proposal, opts).unwrap();
+    println!(
+        "Energy {:.2} -> {:.2} (best {:.2}) in {:.2?}",
+        report.initial_energy(),
+        report.energies().last().copied().unwrap_or(report.initial_energy()),
+        report.best_energy(),
+        report.total_time(),
+    );
 
     println!("Done! Frames saved to frames/");
 }
\ No newline at end of file
@@ -12,4 +12,20 @@ pub enum MrfError{
     NegativeWeight { row: usize, col: usize, value: f64 },
     #[error("Empty StateSpace: StateSpace must be defined over values")]
     EmptyStateSpace,
+    #[error("Node Out Of Range: node {node} does not exist in a graph of {num_nodes} nodes")]
+    NodeOutOfRange { node: usize, num_nodes: usize },
+    #[error("Invalid Parameter: {name} cannot be {value}")]
+    InvalidParameter { name: &'static str, value: f64 },
+    #[error("Label Out Of Range: node {node} has label {label}, expected fewer than {num_labels}")]
+    LabelOutOfRange { node: usize, label: usize, num_labels: usize },
+    #[error("Unsupported Order: cliques of order {order} found, at most {max} supported")]
+    UnsupportedOrder { order: usize, max: usize },
+    #[error("Non-Metric Potential: potential {potential} is not a {required}, violated at labels ({a}, {b})")]
+    NonMetric { potential: usize, required: &'static str, a: usize, b: usize },
+    #[error("Too Many States: {num_labels}^{num_nodes} labellings exceed the limit of {limit}")]
+    TooManyStates { num_labels: usize, num_nodes: usize, limit: u64 },
+    #[error("Not Potts: potential {potential} is not a ferromagnetic Potts interaction")]
+    NotPotts { potential: usize },
+    #[error("Repeated Variable: variable {variable} appears more than once in a factor")]
+    RepeatedVariable { variable: usize },
 }
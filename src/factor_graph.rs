@@ -0,0 +1,224 @@
+use smallvec::SmallVec;
+use crate::graph::{Clique, Graph};
+use crate::model::MRF;
+use crate::state::Label;
+use crate::potentials::CliquePotential;
+use crate::error::MrfError;
+
+/// A factor: a potential over an ordered list of variables
+struct Factor<L> {
+    variables: Vec<usize>,
+    potential: Box<dyn CliquePotential<L>>,
+}
+
+/// A model given as an explicit list of factors rather than graph cliques.
+///
+/// Each factor scores its variables in the order they were declared, so a
+/// `TablePotential` over `[c, a, b]` reads `c` as its first label. No edges or
+/// clique detection are involved; `into_mrf` turns the factors into an `MRF`
+/// for energies, samplers and inference.
+pub struct FactorGraph<L> {
+    num_variables: usize,
+    factors: Vec<Factor<L>>,
+    /// Factors touching each variable, in the order they were added
+    variable_factors: Vec<Vec<usize>>,
+}
+
+impl<L: Label> FactorGraph<L> {
+    pub fn new(num_variables: usize) -> Self {
+        Self {
+            num_variables,
+            factors: Vec::new(),
+            variable_factors: vec![Vec::new(); num_variables],
+        }
+    }
+
+    /// Adds a factor over `variables`, returning its index.
+    /// The list must match the potential's order and name each variable once.
+    pub fn add_factor(
+        &mut self,
+        variables: Vec<usize>,
+        potential: impl CliquePotential<L> + 'static,
+    ) -> Result<usize, MrfError> {
+        if variables.len() != potential.order() {
+            return Err(MrfError::DimensionMismatch { expected: potential.order(), got: variables.len() });
+        }
+        for (i, &v) in variables.iter().enumerate() {
+            if v >= self.num_variables {
+                return Err(MrfError::NodeOutOfRange { node: v, num_nodes: self.num_variables });
+            }
+            if variables[..i].contains(&v) {
+                return Err(MrfError::RepeatedVariable { variable: v });
+            }
+        }
+
+        let index = self.factors.len();
+        for &v in &variables {
+            self.variable_factors[v].push(index);
+        }
+        self.factors.push(Factor { variables, potential: Box::new(potential) });
+        Ok(index)
+    }
+
+    pub fn num_variables(&self) -> usize {
+        self.num_variables
+    }
+
+    pub fn num_factors(&self) -> usize {
+        self.factors.len()
+    }
+
+    /// Variables of a factor, in declared order
+    pub fn factor_variables(&self, factor: usize) -> &[usize] {
+        &self.factors[factor].variables
+    }
+
+    pub fn potential(&self, factor: usize) -> &dyn CliquePotential<L> {
+        self.factors[factor].potential.as_ref()
+    }
+
+    /// Factors that touch a variable, in the order they were added
+    pub fn variable_factors(&self, variable: usize) -> &[usize] {
+        &self.variable_factors[variable]
+    }
+
+    /// Sum of every factor's score under a full labelling
+    pub fn energy(&self, labels: &[L]) -> Result<f64, MrfError> {
+        if labels.len() != self.num_variables {
+            return Err(MrfError::DimensionMismatch { expected: self.num_variables, got: labels.len() });
+        }
+        Ok(self.factors.iter()
+            .map(|f| {
+                let states: SmallVec<[L; 4]> = f.variables.iter().map(|&v| labels[v].clone()).collect();
+                f.potential.score_at(&f.variables, &states)
+            })
+            .sum())
+    }
+}
+
+impl<L: Label + Default> FactorGraph<L> {
+    /// Model with one potential per factor, in factor order, each scoring a single
+    /// clique. Factor `i` is potential `i`, so `MRF::node_cliques` lists a node's
+    /// factors as `(i, 0)`. The graph joins variables that share a factor, for
+    /// algorithms that walk neighbours; it adds no potentials of its own.
+    pub fn into_mrf(self) -> MRF<L> {
+        let mut graph = Graph::new(self.num_variables);
+        let mut potentials: Vec<Box<dyn CliquePotential<L>>> = Vec::with_capacity(self.factors.len());
+        let mut scopes = Vec::with_capacity(self.factors.len());
+        for factor in self.factors {
+            for (i, &a) in factor.variables.iter().enumerate() {
+                for &b in &factor.variables[i + 1..] {
+                    graph.add_edge(a, b);
+                }
+            }
+            let clique = Clique::new(factor.variables.clone());
+            potentials.push(Box::new(Ordered::new(factor, clique.members())));
+            scopes.push(vec![clique]);
+        }
+        MRF::from_scopes(graph, None, potentials, scopes)
+    }
+}
+
+/// Presents a factor to the MRF, which passes states in ascending node order,
+/// by permuting them back to the factor's declared order
+struct Ordered<L> {
+    factor: Factor<L>,
+    /// Position in the sorted clique of each declared variable
+    positions: Vec<usize>,
+}
+
+impl<L> Ordered<L> {
+    fn new(factor: Factor<L>, sorted: &[usize]) -> Self {
+        let positions = factor.variables.iter()
+            .map(|v| sorted.binary_search(v).unwrap())
+            .collect();
+        Self { factor, positions }
+    }
+}
+
+impl<L: Label> CliquePotential<L> for Ordered<L> {
+    fn order(&self) -> usize {
+        self.factor.variables.len()
+    }
+
+    fn num_labels(&self) -> Option<usize> {
+        self.factor.potential.num_labels()
+    }
+
+    fn score(&self, states: &[L]) -> f64 {
+        let declared: SmallVec<[L; 4]> = self.positions.iter().map(|&p| states[p].clone()).collect();
+        self.factor.potential.score_at(&self.factor.variables, &declared)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inference::ExactInference;
+    use crate::potentials::TablePotential;
+    use crate::samplers::{ConstantAnnealer, DiscreteProposal, GibbsSampler, RunOptions, SampleOptions};
+
+    /// Energy l0 + 2 l1 + 4 l2 over binary labels: reveals the order states arrive in
+    fn weighted_triple() -> TablePotential {
+        TablePotential::new((0..8).map(|i| i as f64).collect(), 2, 3).unwrap()
+    }
+
+    #[test]
+    fn rejects_malformed_factors() {
+        let mut fg = FactorGraph::<usize>::new(3);
+        assert!(matches!(
+            fg.add_factor(vec![0, 1], weighted_triple()),
+            Err(MrfError::DimensionMismatch { expected: 3, got: 2 })
+        ));
+        assert!(matches!(
+            fg.add_factor(vec![0, 1, 3], weighted_triple()),
+            Err(MrfError::NodeOutOfRange { node: 3, num_nodes: 3 })
+        ));
+        assert!(matches!(
+            fg.add_factor(vec![2, 0, 2], weighted_triple()),
+            Err(MrfError::RepeatedVariable { variable: 2 })
+        ));
+        assert_eq!(fg.num_factors(), 0);
+    }
+
+    #[test]
+    fn factors_score_in_declared_order() {
+        let mut fg = FactorGraph::<usize>::new(4);
+        let f = fg.add_factor(vec![3, 0, 2], weighted_triple()).unwrap();
+        let g = fg.add_factor(vec![1, 0], TablePotential::new(vec![0.0, 1.0, 10.0, 100.0], 2, 2).unwrap()).unwrap();
+        assert_eq!(fg.factor_variables(f), &[3, 0, 2]);
+        assert_eq!(fg.variable_factors(0), &[f, g]);
+        assert_eq!(fg.variable_factors(3), &[f]);
+
+        // Node 3 is the factor's first label, worth 4; node 1 is the pair's first, worth 10
+        let labels = vec![0, 1, 0, 1];
+        assert_eq!(fg.energy(&labels).unwrap(), 4.0 + 10.0);
+
+        let mut mrf = fg.into_mrf();
+        mrf.set_labels(&labels).unwrap();
+        assert_eq!(mrf.energy(), 14.0);
+        assert_eq!(mrf.node_cliques(0), &[(0, 0), (1, 0)]);
+        assert_eq!(mrf.potential_cliques(0).len(), 1);
+        // Only the declared factors are scored, even though the triple is fully connected
+        assert_eq!(mrf.potentials().len(), 2);
+        assert_eq!(mrf.node_energy(3), 4.0);
+        assert_eq!(mrf.node_energy_with(2, &1), 4.0 + 1.0);
+    }
+
+    #[test]
+    fn gibbs_matches_exact_on_a_factor_list() {
+        let mut fg = FactorGraph::<usize>::new(4);
+        fg.add_factor(vec![2, 0, 1], weighted_triple()).unwrap();
+        fg.add_factor(vec![3, 1], TablePotential::new(vec![0.0, 0.8, 0.3, 0.0], 2, 2).unwrap()).unwrap();
+        fg.add_factor(vec![3], TablePotential::unary(vec![0.5, 0.0])).unwrap();
+        let mut mrf = fg.into_mrf();
+
+        let exact = ExactInference::new(2, 1 << 10).run(&mrf).unwrap();
+        let sampler = GibbsSampler::new(20_000, ConstantAnnealer::new(1.0));
+        let collect = SampleOptions { burn_in: 100, ..Default::default() };
+        let result = sampler.sample(&mut mrf, &DiscreteProposal::new(2), RunOptions { seed: Some(3) }, collect).unwrap();
+        for (node, p) in exact.marginals.iter().enumerate() {
+            assert!((result.probability(node, &1) - p[1]).abs() < 0.02);
+        }
+    }
+}
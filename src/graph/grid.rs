@@ -1,52 +1,231 @@
+use std::collections::HashSet;
 #[allow(unused_imports)]
-use crate::graph::{Connectivity, Neighborhood, Graph, Four, Eight};
+use crate::graph::{Clique, Connectivity, Neighborhood, Graph, Four, Eight, Triangular, Layout, LayoutGraph};
 
-pub struct Grid2D<T> {
+/// Row-major geometry of a 2D lattice.
+/// Kept by an MRF built from a grid so node indices map back to pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GridLayout {
     width: usize,
     height: usize,
+}
+
+impl GridLayout {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height }
+    }
+
+    #[inline]
+    pub fn index(&self, x: usize, y: usize) -> usize {
+        y * self.width + x
+    }
+
+    #[inline]
+    pub fn coords(&self, index: usize) -> (usize, usize) {
+        (index % self.width, index / self.width)
+    }
+
+    #[inline]
+    pub fn shape(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    #[inline]
+    pub fn num_nodes(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// One clique per `kw`×`kh` window lying entirely inside the grid, windows
+    /// overlapping with stride 1. Members are in ascending order, which is
+    /// row-major within the window, so a `TablePotential` of order `kw * kh`
+    /// reads the top-left pixel as its first label.
+    pub fn windows(&self, kw: usize, kh: usize) -> Vec<Clique> {
+        self.wrapped_windows(kw, kh, false, false)
+    }
+
+    /// Windows that may also start near a periodic edge and wrap across it.
+    /// A window spanning a whole periodic axis is listed once, not once per start.
+    fn wrapped_windows(&self, kw: usize, kh: usize, wrap_x: bool, wrap_y: bool) -> Vec<Clique> {
+        if kw == 0 || kh == 0 || kw > self.width || kh > self.height {
+            return Vec::new();
+        }
+        let starts = |k: usize, extent: usize, wrap: bool| if wrap && k < extent { extent } else { extent - k + 1 };
+        let (nx, ny) = (starts(kw, self.width, wrap_x), starts(kh, self.height, wrap_y));
+        let mut cliques = Vec::with_capacity(nx * ny);
+        for y in 0..ny {
+            for x in 0..nx {
+                let members = (y..y + kh)
+                    .flat_map(|wy| (x..x + kw).map(move |wx| self.index(wx % self.width, wy % self.height)))
+                    .collect();
+                cliques.push(Clique::new(members));
+            }
+        }
+        cliques
+    }
+
+    /// Square `k`×`k` windows, e.g. `patches(2)` for every 2×2 pixel block
+    pub fn patches(&self, k: usize) -> Vec<Clique> {
+        self.windows(k, k)
+    }
+}
+
+/// What happens to neighbour offsets that leave the grid
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Boundary {
+    /// Offsets leaving the grid are dropped, so border nodes have fewer neighbours
+    #[default]
+    Open,
+    /// Offsets wrap around in x (left and right edges are joined)
+    PeriodicX,
+    /// Offsets wrap around in y (top and bottom edges are joined)
+    PeriodicY,
+    /// Offsets wrap around in both axes
+    Toroidal,
+}
+
+impl Boundary {
+    pub fn wraps_x(self) -> bool {
+        matches!(self, Boundary::PeriodicX | Boundary::Toroidal)
+    }
+
+    pub fn wraps_y(self) -> bool {
+        matches!(self, Boundary::PeriodicY | Boundary::Toroidal)
+    }
+}
+
+/// Coordinate `c + d` along an axis of length `extent`, wrapped if periodic
+#[inline]
+fn shift(c: usize, d: isize, extent: usize, wrap: bool) -> Option<usize> {
+    let n = c as isize + d;
+    if wrap {
+        Some(n.rem_euclid(extent as isize) as usize)
+    } else if n >= 0 && n < extent as isize {
+        Some(n as usize)
+    } else {
+        None
+    }
+}
+
+pub struct Grid2D<T> {
+    layout: GridLayout,
+    boundary: Boundary,
     graph: Graph<T>,
 }
 impl<T: Default> Grid2D<T> {
     pub fn new(width: usize, height: usize, connectivity: impl Connectivity) -> Self {
-        let num_nodes = width * height;
-        let mut graph = Graph::new(num_nodes);
-
-        let offsets: &[(isize, isize)] = connectivity.offsets();
+        Self::with_boundary(width, height, connectivity, Boundary::Open)
+    }
 
-        for y in 0..height {
-            for x in 0..width {
-                for &(dx, dy) in offsets {
-                    let nx = x as isize + dx;
-                    let ny = y as isize + dy;
-                    if nx >= 0 && nx < width as isize
-                        && ny >= 0 && ny < height as isize
-                    {
-                        // Only add one direction - add_edge adds both
-                        let from = y * width + x;
-                        let to = ny as usize * width + nx as usize;
-                        if from < to {
-                            graph.add_edge(from, to);
-                        }
-                    }
-                }
+    /// Grid whose neighbour offsets wrap around periodic axes.
+    /// Offsets that wrap onto the node itself (an axis of length 1) are dropped.
+    pub fn with_boundary(
+        width: usize,
+        height: usize,
+        connectivity: impl Connectivity,
+        boundary: Boundary,
+    ) -> Self {
+        let mut grid = Self {
+            layout: GridLayout::new(width, height),
+            boundary,
+            graph: Graph::new(width * height),
+        };
+        for &(dx, dy) in connectivity.offsets() {
+            for (from, to) in grid.offset_pairs(dx, dy) {
+                // add_edge adds both directions and ignores duplicates
+                grid.graph.add_edge(from, to);
             }
         }
+        grid
+    }
+
+    /// Consumes the grid, returning its layout and a graph with cliques detected
+    pub fn into_parts(self) -> (GridLayout, Graph<T>) {
+        let mut graph = self.graph;
+        graph.detect_cliques();
+        (self.layout, graph)
+    }
 
-        Self { width, height, graph }
+    /// Consumes the grid, returning a graph with cliques detected
+    pub fn into_graph(self) -> Graph<T> {
+        self.into_parts().1
     }
+}
+
+impl<T> Grid2D<T> {
     #[inline]
     pub fn index(&self, x: usize, y: usize) -> usize {
-        y * self.width + x
+        self.layout.index(x, y)
     }
     
     #[inline]
     pub fn coords(&self, index: usize) -> (usize, usize) {
-        (index % self.width, index / self.width)
+        self.layout.coords(index)
     }
     
     #[inline]
     pub fn shape(&self) -> (usize, usize) {
-        (self.width, self.height)
+        self.layout.shape()
+    }
+
+    /// (from, to) for every node (x, y) whose neighbour (x + dx, y + dy) exists under
+    /// the grid's boundary, excluding offsets that wrap onto the node itself.
+    /// On short periodic axes the wrapped offset can reach a pair already joined
+    /// the other way (e.g. width 2), so each unordered pair is listed once.
+    fn offset_pairs(&self, dx: isize, dy: isize) -> Vec<(usize, usize)> {
+        let (width, height) = self.shape();
+        let mut pairs = Vec::new();
+        let mut seen = HashSet::new();
+        for y in 0..height {
+            for x in 0..width {
+                let nx = shift(x, dx, width, self.boundary.wraps_x());
+                let ny = shift(y, dy, height, self.boundary.wraps_y());
+                if let (Some(nx), Some(ny)) = (nx, ny) {
+                    let (from, to) = (self.index(x, y), self.index(nx, ny));
+                    if from != to && seen.insert((from.min(to), from.max(to))) {
+                        pairs.push((from, to));
+                    }
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Pairwise cliques joining every node (x, y) to (x + dx, y + dy), wrapping
+    /// around periodic axes. Useful for binding direction-specific potentials,
+    /// e.g. `edges_along(1, 0)` for horizontal edges.
+    pub fn edges_along(&self, dx: isize, dy: isize) -> Vec<Clique> {
+        self.offset_pairs(dx, dy).into_iter()
+            .map(|(from, to)| Clique::new(vec![from, to]))
+            .collect()
+    }
+
+    /// Higher-order cliques over every `kw`×`kh` window, for binding with
+    /// `potential_on`. Windows wrap around periodic axes, so on a torus every
+    /// pixel starts one. Members are in ascending node order, so a wrapped
+    /// window's first label is not its top-left pixel.
+    pub fn windows(&self, kw: usize, kh: usize) -> Vec<Clique> {
+        self.layout.wrapped_windows(kw, kh, self.boundary.wraps_x(), self.boundary.wraps_y())
+    }
+
+    /// Cliques over every `k`×`k` block of pixels, wrapping like `windows`
+    pub fn patches(&self, k: usize) -> Vec<Clique> {
+        self.windows(k, k)
+    }
+
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    pub fn layout(&self) -> &GridLayout {
+        &self.layout
+    }
+
+    pub fn graph(&self) -> &Graph<T> {
+        &self.graph
+    }
+
+    pub fn graph_mut(&mut self) -> &mut Graph<T> {
+        &mut self.graph
     }
 }
 impl<T> Neighborhood for Grid2D<T> {
@@ -59,6 +238,13 @@ impl<T> Neighborhood for Grid2D<T> {
     }
 }
 
+impl<T: Default> LayoutGraph<T> for Grid2D<T> {
+    fn into_layout_graph(self) -> (Layout, Graph<T>) {
+        let (layout, graph) = self.into_parts();
+        (Layout::Grid(layout), graph)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -257,4 +443,162 @@ mod tests {
             );
         }
     }
-}
\ No newline at end of file
+
+    // --- Conversion into a graph ---
+
+    #[test]
+    fn into_parts_keeps_layout() {
+        let (layout, graph) = grid4(4, 3).into_parts();
+        assert_eq!(layout.shape(), (4, 3));
+        assert_eq!(layout.index(3, 2), 11);
+        assert_eq!(layout.coords(11), (3, 2));
+        assert_eq!(graph.num_nodes(), 12);
+    }
+
+    #[test]
+    fn into_graph_detects_cliques() {
+        // 4x3 four-connected: 3*3 horizontal + 4*2 vertical edges
+        let graph = grid4(4, 3).into_graph();
+        assert_eq!(graph.maximal_cliques().len(), 17);
+        assert!(graph.maximal_cliques().iter().all(|c| c.len() == 2));
+
+        // Eight-connected 2x2 block forms a single 4-clique
+        let graph = grid8(2, 2).into_graph();
+        assert_eq!(graph.maximal_cliques().len(), 1);
+        assert_eq!(graph.maximal_cliques()[0].len(), 4);
+    }
+
+    // --- Periodic boundaries ---
+
+    #[test]
+    fn toroidal_nodes_all_have_full_neighbourhoods() {
+        let four: Grid2D<()> = Grid2D::with_boundary(5, 4, Four, Boundary::Toroidal);
+        let eight: Grid2D<()> = Grid2D::with_boundary(5, 4, Eight, Boundary::Toroidal);
+        for i in 0..20 {
+            assert_eq!(four.neighbors(i).len(), 4);
+            assert_eq!(eight.neighbors(i).len(), 8);
+        }
+        let mut n = four.neighbors(0).to_vec();
+        n.sort();
+        // (0,0) wraps to (4,0)=4 and (0,3)=15
+        assert_eq!(n, vec![1, 4, 5, 15]);
+    }
+
+    #[test]
+    fn periodic_in_one_axis() {
+        let x: Grid2D<()> = Grid2D::with_boundary(4, 4, Four, Boundary::PeriodicX);
+        let y: Grid2D<()> = Grid2D::with_boundary(4, 4, Four, Boundary::PeriodicY);
+        // Top-left corner gains only the wrapped neighbour along the periodic axis
+        assert!(x.neighbors(0).contains(&3) && !x.neighbors(0).contains(&12));
+        assert!(y.neighbors(0).contains(&12) && !y.neighbors(0).contains(&3));
+        assert_eq!(x.neighbors(x.index(1, 0)).len(), 3);
+        assert_eq!(x.neighbors(x.index(0, 1)).len(), 4);
+        assert_eq!(y.neighbors(y.index(0, 1)).len(), 3);
+    }
+
+    #[test]
+    fn periodic_edges_along_wrap() {
+        let grid: Grid2D<()> = Grid2D::with_boundary(4, 3, Four, Boundary::PeriodicX);
+        assert_eq!(grid.edges_along(1, 0).len(), 12);
+        assert_eq!(grid.edges_along(0, 1).len(), 8);
+        assert!(grid.edges_along(1, 0).iter().any(|c| c.members() == [0, 3]));
+        assert_eq!(grid.boundary(), Boundary::PeriodicX);
+
+        // An axis of length 1 has no wrapped self-edges
+        let line: Grid2D<()> = Grid2D::with_boundary(4, 1, Four, Boundary::Toroidal);
+        assert!(line.edges_along(0, 1).is_empty());
+        assert_eq!(line.neighbors(0).len(), 2);
+    }
+
+    #[test]
+    fn toroidal_graph_has_uniform_cliques() {
+        // 4x4 torus: every node has two horizontal and two vertical edges
+        let graph = Grid2D::<()>::with_boundary(4, 4, Four, Boundary::Toroidal).into_graph();
+        assert_eq!(graph.maximal_cliques().len(), 32);
+        assert!(graph.maximal_cliques().iter().all(|c| c.len() == 2));
+    }
+
+    #[test]
+    fn width_two_torus_lists_each_edge_once() {
+        use crate::MRF;
+        use crate::potentials::TablePotential;
+
+        // On a 2-wide axis, x + 1 and the wrap-around x - 1 are the same neighbour
+        let grid: Grid2D<usize> = Grid2D::with_boundary(2, 3, Four, Boundary::Toroidal);
+        let horizontal = grid.edges_along(1, 0);
+        assert_eq!(horizontal.len(), 3);
+        assert_eq!(grid.edges_along(0, 1).len(), 6);
+        assert_eq!(grid.edges_along(1, 1).len(), 6);
+
+        let disagree = TablePotential::new(vec![0.0, 1.0, 1.0, 0.0], 2, 2).unwrap();
+        let mut mrf = MRF::<usize>::builder().grid(grid).potential_on(disagree, horizontal).build().unwrap();
+        mrf.set_labels(&[0, 1, 0, 1, 0, 1]).unwrap();
+        assert_eq!(mrf.energy(), 3.0);
+    }
+
+    // --- Triangular connectivity ---
+
+    #[test]
+    fn triangular_neighbourhoods() {
+        let grid: Grid2D<()> = Grid2D::new(4, 4, Triangular);
+        assert_eq!(grid.neighbors(grid.index(1, 1)).len(), 6);
+        assert_eq!(grid.neighbors(grid.index(0, 0)).len(), 3);
+        assert_eq!(grid.neighbors(grid.index(3, 0)).len(), 2);
+        let mut n = grid.neighbors(5).to_vec();
+        n.sort();
+        // (1,1): (0,0)=0, (1,0)=1, (0,1)=4, (2,1)=6, (1,2)=9, (2,2)=10
+        assert_eq!(n, vec![0, 1, 4, 6, 9, 10]);
+    }
+
+    #[test]
+    fn triangular_cliques_are_triangles() {
+        // Two triangles per unit square
+        let graph = Grid2D::<()>::new(4, 3, Triangular).into_graph();
+        assert_eq!(graph.maximal_cliques().len(), 2 * 3 * 2);
+        assert!(graph.maximal_cliques().iter().all(|c| c.len() == 3));
+        assert_eq!(graph.cliques_of_order(2).len(), 3 * 3 + 4 * 2 + 3 * 2);
+
+        let torus = Grid2D::<()>::with_boundary(4, 4, Triangular, Boundary::Toroidal).into_graph();
+        assert_eq!(torus.maximal_cliques().len(), 2 * 16);
+    }
+
+    // --- Patch cliques ---
+
+    #[test]
+    fn windows_cover_every_block() {
+        let grid = grid4(4, 3);
+        let patches = grid.patches(2);
+        assert_eq!(patches.len(), 3 * 2);
+        // Top-left block is row-major within the window
+        assert_eq!(patches[0].members(), &[0, 1, 4, 5]);
+        assert_eq!(patches[5].members(), &[6, 7, 10, 11]);
+        assert!(patches.iter().all(|c| c.len() == 4));
+
+        let wide = grid.windows(3, 1);
+        assert_eq!(wide.len(), 2 * 3);
+        assert_eq!(wide[1].members(), &[1, 2, 3]);
+        assert_eq!(grid.patches(3).len(), 2);
+        assert!(grid.patches(4).is_empty());
+        assert!(grid.windows(0, 2).is_empty());
+
+    }
+
+    #[test]
+    fn windows_wrap_periodic_axes() {
+        let torus: Grid2D<()> = Grid2D::with_boundary(4, 3, Four, Boundary::Toroidal);
+        let patches = torus.patches(2);
+        assert_eq!(patches.len(), 4 * 3);
+        assert!(patches.iter().all(|c| c.len() == 4));
+        // Bottom-right block wraps to the top-left corner
+        assert_eq!(patches[11].members(), &[0, 3, 8, 11]);
+        for node in 0..12 {
+            assert_eq!(patches.iter().filter(|c| c.contains(node)).count(), 4);
+        }
+
+        let strip: Grid2D<()> = Grid2D::with_boundary(4, 3, Four, Boundary::PeriodicX);
+        assert_eq!(strip.patches(2).len(), 4 * 2);
+        // A window as wide as the periodic axis is listed once per row
+        assert_eq!(strip.windows(4, 1).len(), 3);
+        assert!(torus.patches(4).is_empty());
+    }
+}
@@ -0,0 +1,245 @@
+use crate::graph::{Clique, Graph, Layout, LatticeLayout, LayoutGraph, Neighborhood};
+
+/// Row-major geometry of a 3D lattice: x fastest, then y, then z.
+/// Kept by an MRF built from a volume so node indices map back to voxels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VolumeLayout {
+    width: usize,
+    height: usize,
+    depth: usize,
+}
+
+impl VolumeLayout {
+    pub fn new(width: usize, height: usize, depth: usize) -> Self {
+        Self { width, height, depth }
+    }
+
+    #[inline]
+    pub fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        (z * self.height + y) * self.width + x
+    }
+
+    #[inline]
+    pub fn coords(&self, index: usize) -> (usize, usize, usize) {
+        let plane = self.width * self.height;
+        (index % self.width, index % plane / self.width, index / plane)
+    }
+
+    #[inline]
+    pub fn shape(&self) -> (usize, usize, usize) {
+        (self.width, self.height, self.depth)
+    }
+
+    #[inline]
+    pub fn num_nodes(&self) -> usize {
+        self.width * self.height * self.depth
+    }
+
+    fn lattice(&self) -> LatticeLayout {
+        LatticeLayout::new(&[self.width, self.height, self.depth])
+    }
+}
+
+/// Convenient for constructing volumes
+pub trait Connectivity3D {
+    fn offsets(&self) -> &[(isize, isize, isize)];
+}
+/// Face neighbours
+pub struct Six;
+/// Face and edge neighbours
+pub struct Eighteen;
+/// Face, edge and corner neighbours
+pub struct TwentySix;
+
+impl Connectivity3D for Six {
+    fn offsets(&self) -> &'static [(isize, isize, isize)] {
+        &[(0, 0, -1), (0, -1, 0), (-1, 0, 0), (1, 0, 0), (0, 1, 0), (0, 0, 1)]
+    }
+}
+
+impl Connectivity3D for Eighteen {
+    fn offsets(&self) -> &'static [(isize, isize, isize)] {
+        &[
+                        (0, -1, -1),
+            (-1, 0, -1), (0, 0, -1), (1, 0, -1),
+                        (0, 1, -1),
+
+            (-1, -1, 0), (0, -1, 0), (1, -1, 0),
+            (-1,  0, 0),             (1,  0, 0),
+            (-1,  1, 0), (0,  1, 0), (1,  1, 0),
+
+                        (0, -1, 1),
+            (-1, 0, 1), (0, 0, 1), (1, 0, 1),
+                        (0, 1, 1),
+        ]
+    }
+}
+
+impl Connectivity3D for TwentySix {
+    fn offsets(&self) -> &'static [(isize, isize, isize)] {
+        &[
+            (-1, -1, -1), (0, -1, -1), (1, -1, -1),
+            (-1,  0, -1), (0,  0, -1), (1,  0, -1),
+            (-1,  1, -1), (0,  1, -1), (1,  1, -1),
+
+            (-1, -1, 0), (0, -1, 0), (1, -1, 0),
+            (-1,  0, 0),             (1,  0, 0),
+            (-1,  1, 0), (0,  1, 0), (1,  1, 0),
+
+            (-1, -1, 1), (0, -1, 1), (1, -1, 1),
+            (-1,  0, 1), (0,  0, 1), (1,  0, 1),
+            (-1,  1, 1), (0,  1, 1), (1,  1, 1),
+        ]
+    }
+}
+
+pub struct Grid3D<T> {
+    layout: VolumeLayout,
+    graph: Graph<T>,
+}
+
+impl<T: Default> Grid3D<T> {
+    pub fn new(width: usize, height: usize, depth: usize, connectivity: impl Connectivity3D) -> Self {
+        let layout = VolumeLayout::new(width, height, depth);
+        let offsets: Vec<[isize; 3]> = connectivity.offsets().iter()
+            .map(|&(dx, dy, dz)| [dx, dy, dz])
+            .collect();
+        let graph = layout.lattice().build_graph(&offsets);
+        Self { layout, graph }
+    }
+
+    /// Consumes the volume, returning its layout and a graph with cliques detected
+    pub fn into_parts(self) -> (VolumeLayout, Graph<T>) {
+        let mut graph = self.graph;
+        graph.detect_cliques();
+        (self.layout, graph)
+    }
+
+    /// Consumes the volume, returning a graph with cliques detected
+    pub fn into_graph(self) -> Graph<T> {
+        self.into_parts().1
+    }
+}
+
+impl<T> Grid3D<T> {
+    #[inline]
+    pub fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        self.layout.index(x, y, z)
+    }
+
+    #[inline]
+    pub fn coords(&self, index: usize) -> (usize, usize, usize) {
+        self.layout.coords(index)
+    }
+
+    #[inline]
+    pub fn shape(&self) -> (usize, usize, usize) {
+        self.layout.shape()
+    }
+
+    /// Pairwise cliques joining every voxel (x, y, z) to (x + dx, y + dy, z + dz) inside the volume
+    pub fn edges_along(&self, dx: isize, dy: isize, dz: isize) -> Vec<Clique> {
+        self.layout.lattice().offset_pairs(&[dx, dy, dz]).into_iter()
+            .map(|(from, to)| Clique::new(vec![from, to]))
+            .collect()
+    }
+
+    pub fn layout(&self) -> &VolumeLayout {
+        &self.layout
+    }
+
+    pub fn graph(&self) -> &Graph<T> {
+        &self.graph
+    }
+
+    pub fn graph_mut(&mut self) -> &mut Graph<T> {
+        &mut self.graph
+    }
+}
+
+impl<T> Neighborhood for Grid3D<T> {
+    fn neighbors(&self, index: usize) -> &[usize] {
+        self.graph.neighbors(index)
+    }
+    #[inline]
+    fn num_nodes(&self) -> usize {
+        self.graph.num_nodes()
+    }
+}
+
+impl<T: Default> LayoutGraph<T> for Grid3D<T> {
+    fn into_layout_graph(self) -> (Layout, Graph<T>) {
+        let (layout, graph) = self.into_parts();
+        (Layout::Volume(layout), graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_coords_roundtrip() {
+        let grid: Grid3D<()> = Grid3D::new(4, 3, 2, Six);
+        for z in 0..2 {
+            for y in 0..3 {
+                for x in 0..4 {
+                    assert_eq!(grid.coords(grid.index(x, y, z)), (x, y, z));
+                }
+            }
+        }
+        assert_eq!(grid.index(1, 0, 0), 1);
+        assert_eq!(grid.index(0, 1, 0), 4);
+        assert_eq!(grid.index(0, 0, 1), 12);
+        assert_eq!(grid.num_nodes(), 24);
+    }
+
+    #[test]
+    fn neighbour_counts() {
+        let six: Grid3D<()> = Grid3D::new(3, 3, 3, Six);
+        let eighteen: Grid3D<()> = Grid3D::new(3, 3, 3, Eighteen);
+        let twenty_six: Grid3D<()> = Grid3D::new(3, 3, 3, TwentySix);
+        let centre = six.index(1, 1, 1);
+        assert_eq!(six.neighbors(centre).len(), 6);
+        assert_eq!(eighteen.neighbors(centre).len(), 18);
+        assert_eq!(twenty_six.neighbors(centre).len(), 26);
+        // Corner voxel
+        assert_eq!(six.neighbors(0).len(), 3);
+        assert_eq!(eighteen.neighbors(0).len(), 6);
+        assert_eq!(twenty_six.neighbors(0).len(), 7);
+    }
+
+    #[test]
+    fn six_face_neighbors() {
+        let grid: Grid3D<()> = Grid3D::new(3, 3, 3, Six);
+        let mut n = grid.neighbors(grid.index(1, 1, 1)).to_vec();
+        n.sort();
+        assert_eq!(n, vec![4, 10, 12, 14, 16, 22]);
+    }
+
+    #[test]
+    fn neighbor_symmetry() {
+        let grid: Grid3D<()> = Grid3D::new(4, 3, 3, TwentySix);
+        for i in 0..grid.num_nodes() {
+            assert!(!grid.neighbors(i).contains(&i));
+            for &j in grid.neighbors(i) {
+                assert!(grid.neighbors(j).contains(&i));
+            }
+        }
+    }
+
+    #[test]
+    fn into_graph_detects_cliques() {
+        // 3x3x2 six-connected: 2*3*2 along x, 3*2*2 along y, 3*3 along z
+        let grid: Grid3D<()> = Grid3D::new(3, 3, 2, Six);
+        assert_eq!(grid.edges_along(0, 0, 1).len(), 9);
+        let graph = grid.into_graph();
+        assert_eq!(graph.maximal_cliques().len(), 12 + 12 + 9);
+        assert!(graph.maximal_cliques().iter().all(|c| c.len() == 2));
+
+        // A 2x2x2 block is one 8-clique under 26-connectivity
+        let graph = Grid3D::<()>::new(2, 2, 2, TwentySix).into_graph();
+        assert_eq!(graph.maximal_cliques().len(), 1);
+        assert_eq!(graph.maximal_cliques()[0].len(), 8);
+    }
+}
@@ -0,0 +1,278 @@
+use crate::graph::{Clique, Graph, Layout, LayoutGraph, Neighborhood};
+
+/// Axial offsets of the six neighbours of a hex tile
+const AXIAL_NEIGHBORS: [(isize, isize); 6] = [(1, 0), (1, -1), (0, -1), (-1, 0), (-1, 1), (0, 1)];
+
+/// Which rows (pointy-top) or columns (flat-top) of a rectangular hex map are
+/// shoved by half a tile, as in the usual "offset coordinates"
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HexOffset {
+    /// Pointy-top tiles, odd rows shifted right
+    OddR,
+    /// Pointy-top tiles, even rows shifted right
+    EvenR,
+    /// Flat-top tiles, odd columns shifted down
+    OddQ,
+    /// Flat-top tiles, even columns shifted down
+    EvenQ,
+}
+
+/// Row-major geometry of a rectangular hex map in offset coordinates (col, row),
+/// with conversions to axial coordinates (q, r) where neighbours are fixed offsets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HexLayout {
+    width: usize,
+    height: usize,
+    offset: HexOffset,
+}
+
+impl HexLayout {
+    pub fn new(width: usize, height: usize, offset: HexOffset) -> Self {
+        Self { width, height, offset }
+    }
+
+    #[inline]
+    pub fn index(&self, col: usize, row: usize) -> usize {
+        row * self.width + col
+    }
+
+    #[inline]
+    pub fn coords(&self, index: usize) -> (usize, usize) {
+        (index % self.width, index / self.width)
+    }
+
+    #[inline]
+    pub fn shape(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    #[inline]
+    pub fn num_nodes(&self) -> usize {
+        self.width * self.height
+    }
+
+    pub fn offset(&self) -> HexOffset {
+        self.offset
+    }
+
+    /// Offset (col, row) to axial (q, r)
+    pub fn to_axial(&self, col: usize, row: usize) -> (isize, isize) {
+        let (col, row) = (col as isize, row as isize);
+        match self.offset {
+            HexOffset::OddR => (col - (row - (row & 1)) / 2, row),
+            HexOffset::EvenR => (col - (row + (row & 1)) / 2, row),
+            HexOffset::OddQ => (col, row - (col - (col & 1)) / 2),
+            HexOffset::EvenQ => (col, row - (col + (col & 1)) / 2),
+        }
+    }
+
+    /// Axial (q, r) to offset (col, row), if the tile lies on the map
+    pub fn from_axial(&self, q: isize, r: isize) -> Option<(usize, usize)> {
+        let (col, row) = match self.offset {
+            HexOffset::OddR => (q + (r - (r & 1)) / 2, r),
+            HexOffset::EvenR => (q + (r + (r & 1)) / 2, r),
+            HexOffset::OddQ => (q, r + (q - (q & 1)) / 2),
+            HexOffset::EvenQ => (q, r + (q + (q & 1)) / 2),
+        };
+        let inside = col >= 0 && col < self.width as isize && row >= 0 && row < self.height as isize;
+        inside.then_some((col as usize, row as usize))
+    }
+
+    /// Axial coordinates of a node
+    pub fn axial(&self, index: usize) -> (isize, isize) {
+        let (col, row) = self.coords(index);
+        self.to_axial(col, row)
+    }
+
+    /// Node at axial coordinates, if on the map
+    pub fn axial_index(&self, q: isize, r: isize) -> Option<usize> {
+        self.from_axial(q, r).map(|(col, row)| self.index(col, row))
+    }
+
+    /// Cube coordinates (x, y, z) with x + y + z = 0
+    pub fn cube(&self, index: usize) -> (isize, isize, isize) {
+        let (q, r) = self.axial(index);
+        (q, r, -q - r)
+    }
+
+    /// Number of steps between two tiles
+    pub fn distance(&self, a: usize, b: usize) -> usize {
+        let (qa, ra) = self.axial(a);
+        let (qb, rb) = self.axial(b);
+        let (dq, dr) = (qa - qb, ra - rb);
+        (dq.unsigned_abs() + dr.unsigned_abs() + (dq + dr).unsigned_abs()) / 2
+    }
+
+    /// (from, to) for every tile whose neighbour at axial offset (dq, dr) is on the map
+    fn offset_pairs(&self, dq: isize, dr: isize) -> Vec<(usize, usize)> {
+        (0..self.num_nodes())
+            .filter_map(|from| {
+                let (q, r) = self.axial(from);
+                self.axial_index(q + dq, r + dr).map(|to| (from, to))
+            })
+            .collect()
+    }
+}
+
+/// Hexagonal tile map. Each tile touches up to six others, and every three tiles
+/// meeting at a corner form a 3-clique.
+pub struct HexGrid<T> {
+    layout: HexLayout,
+    graph: Graph<T>,
+}
+
+impl<T: Default> HexGrid<T> {
+    pub fn new(width: usize, height: usize, offset: HexOffset) -> Self {
+        let layout = HexLayout::new(width, height, offset);
+        let mut graph = Graph::new(layout.num_nodes());
+        for &(dq, dr) in &AXIAL_NEIGHBORS {
+            for (from, to) in layout.offset_pairs(dq, dr) {
+                graph.add_edge(from, to);
+            }
+        }
+        Self { layout, graph }
+    }
+
+    /// Consumes the map, returning its layout and a graph with cliques detected
+    pub fn into_parts(self) -> (HexLayout, Graph<T>) {
+        let mut graph = self.graph;
+        graph.detect_cliques();
+        (self.layout, graph)
+    }
+
+    /// Consumes the map, returning a graph with cliques detected
+    pub fn into_graph(self) -> Graph<T> {
+        self.into_parts().1
+    }
+}
+
+impl<T> HexGrid<T> {
+    #[inline]
+    pub fn index(&self, col: usize, row: usize) -> usize {
+        self.layout.index(col, row)
+    }
+
+    #[inline]
+    pub fn coords(&self, index: usize) -> (usize, usize) {
+        self.layout.coords(index)
+    }
+
+    #[inline]
+    pub fn shape(&self) -> (usize, usize) {
+        self.layout.shape()
+    }
+
+    /// Pairwise cliques joining every tile to its neighbour at axial offset (dq, dr),
+    /// e.g. `edges_along(1, 0)` for one of the three edge directions
+    pub fn edges_along(&self, dq: isize, dr: isize) -> Vec<Clique> {
+        self.layout.offset_pairs(dq, dr).into_iter()
+            .map(|(from, to)| Clique::new(vec![from, to]))
+            .collect()
+    }
+
+    pub fn layout(&self) -> &HexLayout {
+        &self.layout
+    }
+
+    pub fn graph(&self) -> &Graph<T> {
+        &self.graph
+    }
+
+    pub fn graph_mut(&mut self) -> &mut Graph<T> {
+        &mut self.graph
+    }
+}
+
+impl<T> Neighborhood for HexGrid<T> {
+    fn neighbors(&self, index: usize) -> &[usize] {
+        self.graph.neighbors(index)
+    }
+    #[inline]
+    fn num_nodes(&self) -> usize {
+        self.graph.num_nodes()
+    }
+}
+
+impl<T: Default> LayoutGraph<T> for HexGrid<T> {
+    fn into_layout_graph(self) -> (Layout, Graph<T>) {
+        let (layout, graph) = self.into_parts();
+        (Layout::Hex(layout), graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OFFSETS: [HexOffset; 4] = [HexOffset::OddR, HexOffset::EvenR, HexOffset::OddQ, HexOffset::EvenQ];
+
+    #[test]
+    fn axial_roundtrip() {
+        for offset in OFFSETS {
+            let layout = HexLayout::new(5, 4, offset);
+            for i in 0..layout.num_nodes() {
+                let (q, r) = layout.axial(i);
+                assert_eq!(layout.axial_index(q, r), Some(i));
+                let (x, y, z) = layout.cube(i);
+                assert_eq!(x + y + z, 0);
+            }
+            assert_eq!(layout.axial_index(-10, 0), None);
+        }
+    }
+
+    #[test]
+    fn odd_r_neighbours() {
+        let grid: HexGrid<()> = HexGrid::new(4, 4, HexOffset::OddR);
+        // Even row: (1,2) touches (0,1), (1,1), (0,2), (2,2), (0,3), (1,3)
+        let mut n = grid.neighbors(grid.index(1, 2)).to_vec();
+        n.sort();
+        assert_eq!(n, vec![4, 5, 8, 10, 12, 13]);
+        // Odd row: (1,1) touches (1,0), (2,0), (0,1), (2,1), (1,2), (2,2)
+        let mut n = grid.neighbors(grid.index(1, 1)).to_vec();
+        n.sort();
+        assert_eq!(n, vec![1, 2, 4, 6, 9, 10]);
+    }
+
+    #[test]
+    fn neighbour_counts_and_distance() {
+        for offset in OFFSETS {
+            let grid: HexGrid<()> = HexGrid::new(5, 5, offset);
+            let centre = grid.index(2, 2);
+            assert_eq!(grid.neighbors(centre).len(), 6);
+            for &n in grid.neighbors(centre) {
+                assert_eq!(grid.layout().distance(centre, n), 1);
+                assert!(grid.neighbors(n).contains(&centre));
+            }
+            for i in 0..grid.num_nodes() {
+                assert!((2..=6).contains(&grid.neighbors(i).len()));
+            }
+        }
+        let layout = HexLayout::new(6, 6, HexOffset::OddR);
+        assert_eq!(layout.distance(layout.index(0, 0), layout.index(5, 0)), 5);
+        assert_eq!(layout.distance(layout.index(0, 0), layout.index(0, 4)), 4);
+    }
+
+    #[test]
+    fn corners_form_triangles() {
+        let grid: HexGrid<()> = HexGrid::new(4, 3, HexOffset::OddR);
+        assert_eq!(grid.edges_along(1, 0).len(), 9);
+        let graph = grid.into_graph();
+        assert!(graph.maximal_cliques().iter().all(|c| c.len() == 3));
+        // Adjacent rows of 4 tiles meet at 2 * 4 - 2 inner corners
+        assert_eq!(graph.maximal_cliques().len(), 2 * 6);
+    }
+
+    #[test]
+    fn builds_an_mrf_with_triangle_potentials() {
+        use crate::MRF;
+        use crate::potentials::TablePotential;
+
+        let grid: HexGrid<usize> = HexGrid::new(3, 3, HexOffset::EvenQ);
+        let triple = TablePotential::new((0..8).map(|i| i as f64).collect(), 2, 3).unwrap();
+        let mrf = MRF::<usize>::builder().grid(grid).potential(triple).build().unwrap();
+        assert!(!mrf.potential_cliques(0).is_empty());
+        assert!(mrf.potential_cliques(0).iter().all(|c| c.len() == 3));
+        let layout = mrf.layout().and_then(Layout::as_hex).unwrap();
+        assert_eq!(layout.offset(), HexOffset::EvenQ);
+    }
+}
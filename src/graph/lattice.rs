@@ -0,0 +1,325 @@
+use crate::graph::{Clique, Graph, Layout, LayoutGraph, Neighborhood};
+use crate::error::MrfError;
+
+/// Row-major geometry of an N-dimensional lattice, first axis fastest.
+/// In 2D this matches `GridLayout` with coordinates (x, y).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LatticeLayout {
+    shape: Vec<usize>,
+    strides: Vec<usize>,
+}
+
+impl LatticeLayout {
+    pub fn new(shape: &[usize]) -> Self {
+        let mut strides = Vec::with_capacity(shape.len());
+        let mut stride = 1;
+        for &extent in shape {
+            strides.push(stride);
+            stride *= extent;
+        }
+        Self { shape: shape.to_vec(), strides }
+    }
+
+    #[inline]
+    pub fn index(&self, coords: &[usize]) -> usize {
+        coords.iter().zip(&self.strides).map(|(c, s)| c * s).sum()
+    }
+
+    pub fn coords(&self, index: usize) -> Vec<usize> {
+        self.shape.iter().zip(&self.strides).map(|(extent, stride)| index / stride % extent).collect()
+    }
+
+    #[inline]
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    #[inline]
+    pub fn dims(&self) -> usize {
+        self.shape.len()
+    }
+
+    #[inline]
+    pub fn num_nodes(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    /// (from, to) for every node whose neighbour at `offset` lies inside the lattice
+    pub fn offset_pairs(&self, offset: &[isize]) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        if self.num_nodes() == 0 {
+            return pairs;
+        }
+        let mut coords = vec![0; self.dims()];
+        for from in 0..self.num_nodes() {
+            let mut to = from as isize;
+            let inside = coords.iter().zip(offset).zip(&self.shape).zip(&self.strides)
+                .all(|(((&c, &d), &extent), &stride)| {
+                    let n = c as isize + d;
+                    to += d * stride as isize;
+                    n >= 0 && n < extent as isize
+                });
+            if inside {
+                pairs.push((from, to as usize));
+            }
+            // Advance coordinates, first axis fastest
+            for (c, &extent) in coords.iter_mut().zip(&self.shape) {
+                *c += 1;
+                if *c < extent {
+                    break;
+                }
+                *c = 0;
+            }
+        }
+        pairs
+    }
+
+    /// Graph joining every node to its neighbours at the given offsets
+    pub(crate) fn build_graph<T: Default, O: AsRef<[isize]>>(&self, offsets: &[O]) -> Graph<T> {
+        let mut graph = Graph::new(self.num_nodes());
+        for offset in offsets {
+            for (from, to) in self.offset_pairs(offset.as_ref()) {
+                graph.add_edge(from, to);
+            }
+        }
+        graph
+    }
+}
+
+/// Neighbour offsets of an N-dimensional lattice
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stencil {
+    dims: usize,
+    offsets: Vec<Vec<isize>>,
+}
+
+impl Stencil {
+    /// Arbitrary offsets; each must have `dims` entries and at least one non-zero.
+    /// An offset and its negation describe the same edges.
+    pub fn new(dims: usize, offsets: Vec<Vec<isize>>) -> Result<Self, MrfError> {
+        for offset in &offsets {
+            if offset.len() != dims {
+                return Err(MrfError::DimensionMismatch { expected: dims, got: offset.len() });
+            }
+            if offset.iter().all(|&d| d == 0) {
+                return Err(MrfError::InvalidParameter { name: "offset", value: 0.0 });
+            }
+        }
+        Ok(Self { dims, offsets })
+    }
+
+    /// Face neighbours: ±1 along one axis (4 in 2D, 6 in 3D)
+    pub fn von_neumann(dims: usize) -> Self {
+        Self::within(dims, 1)
+    }
+
+    /// Every neighbour in the surrounding 3^N block (8 in 2D, 26 in 3D)
+    pub fn moore(dims: usize) -> Self {
+        Self::within(dims, dims)
+    }
+
+    /// Offsets in {-1, 0, 1}^N with between 1 and `max_axes` non-zero entries,
+    /// e.g. `within(3, 2)` is the 18-neighbourhood
+    pub fn within(dims: usize, max_axes: usize) -> Self {
+        let mut offsets = Vec::new();
+        let mut offset = vec![-1isize; dims];
+        for _ in 0..3usize.pow(dims as u32) {
+            let moved = offset.iter().filter(|&&d| d != 0).count();
+            if moved >= 1 && moved <= max_axes {
+                offsets.push(offset.clone());
+            }
+            for d in offset.iter_mut().rev() {
+                *d += 1;
+                if *d <= 1 {
+                    break;
+                }
+                *d = -1;
+            }
+        }
+        Self { dims, offsets }
+    }
+
+    pub fn dims(&self) -> usize {
+        self.dims
+    }
+
+    pub fn offsets(&self) -> &[Vec<isize>] {
+        &self.offsets
+    }
+}
+
+/// N-dimensional lattice graph, e.g. 2D + time video or 4D volumes over time
+pub struct Lattice<T> {
+    layout: LatticeLayout,
+    graph: Graph<T>,
+}
+
+impl<T: Default> Lattice<T> {
+    pub fn new(shape: &[usize], stencil: &Stencil) -> Result<Self, MrfError> {
+        if stencil.dims() != shape.len() {
+            return Err(MrfError::DimensionMismatch { expected: shape.len(), got: stencil.dims() });
+        }
+        let layout = LatticeLayout::new(shape);
+        let graph = layout.build_graph(stencil.offsets());
+        Ok(Self { layout, graph })
+    }
+
+    /// Consumes the lattice, returning its layout and a graph with cliques detected
+    pub fn into_parts(self) -> (LatticeLayout, Graph<T>) {
+        let mut graph = self.graph;
+        graph.detect_cliques();
+        (self.layout, graph)
+    }
+
+    /// Consumes the lattice, returning a graph with cliques detected
+    pub fn into_graph(self) -> Graph<T> {
+        self.into_parts().1
+    }
+}
+
+impl<T> Lattice<T> {
+    #[inline]
+    pub fn index(&self, coords: &[usize]) -> usize {
+        self.layout.index(coords)
+    }
+
+    pub fn coords(&self, index: usize) -> Vec<usize> {
+        self.layout.coords(index)
+    }
+
+    #[inline]
+    pub fn shape(&self) -> &[usize] {
+        self.layout.shape()
+    }
+
+    /// Pairwise cliques joining every node to its neighbour at `offset` inside the lattice
+    pub fn edges_along(&self, offset: &[isize]) -> Vec<Clique> {
+        self.layout.offset_pairs(offset).into_iter()
+            .map(|(from, to)| Clique::new(vec![from, to]))
+            .collect()
+    }
+
+    pub fn layout(&self) -> &LatticeLayout {
+        &self.layout
+    }
+
+    pub fn graph(&self) -> &Graph<T> {
+        &self.graph
+    }
+
+    pub fn graph_mut(&mut self) -> &mut Graph<T> {
+        &mut self.graph
+    }
+}
+
+impl<T> Neighborhood for Lattice<T> {
+    fn neighbors(&self, index: usize) -> &[usize] {
+        self.graph.neighbors(index)
+    }
+    #[inline]
+    fn num_nodes(&self) -> usize {
+        self.graph.num_nodes()
+    }
+}
+
+impl<T: Default> LayoutGraph<T> for Lattice<T> {
+    fn into_layout_graph(self) -> (Layout, Graph<T>) {
+        let (layout, graph) = self.into_parts();
+        (Layout::Lattice(layout), graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Four, Grid2D};
+
+    fn sorted_neighbors<T>(lattice: &Lattice<T>, index: usize) -> Vec<usize> {
+        let mut n = lattice.neighbors(index).to_vec();
+        n.sort();
+        n
+    }
+
+    #[test]
+    fn index_coords_roundtrip() {
+        let layout = LatticeLayout::new(&[3, 4, 2, 5]);
+        assert_eq!(layout.num_nodes(), 120);
+        for i in 0..layout.num_nodes() {
+            assert_eq!(layout.index(&layout.coords(i)), i);
+        }
+        assert_eq!(layout.index(&[1, 0, 0, 0]), 1);
+        assert_eq!(layout.index(&[0, 1, 0, 0]), 3);
+        assert_eq!(layout.index(&[0, 0, 0, 1]), 24);
+    }
+
+    #[test]
+    fn stencil_sizes() {
+        assert_eq!(Stencil::von_neumann(2).offsets().len(), 4);
+        assert_eq!(Stencil::moore(2).offsets().len(), 8);
+        assert_eq!(Stencil::von_neumann(3).offsets().len(), 6);
+        assert_eq!(Stencil::within(3, 2).offsets().len(), 18);
+        assert_eq!(Stencil::moore(3).offsets().len(), 26);
+        assert_eq!(Stencil::moore(4).offsets().len(), 80);
+    }
+
+    #[test]
+    fn stencil_validates_offsets() {
+        assert!(matches!(
+            Stencil::new(3, vec![vec![1, 0]]),
+            Err(MrfError::DimensionMismatch { expected: 3, got: 2 })
+        ));
+        assert!(matches!(Stencil::new(2, vec![vec![0, 0]]), Err(MrfError::InvalidParameter { .. })));
+        assert!(matches!(
+            Lattice::<()>::new(&[3, 3], &Stencil::von_neumann(3)),
+            Err(MrfError::DimensionMismatch { expected: 2, got: 3 })
+        ));
+    }
+
+    #[test]
+    fn matches_grid2d() {
+        let lattice: Lattice<()> = Lattice::new(&[5, 4], &Stencil::von_neumann(2)).unwrap();
+        let grid: Grid2D<()> = Grid2D::new(5, 4, Four);
+        for i in 0..20 {
+            let mut expected = grid.neighbors(i).to_vec();
+            expected.sort();
+            assert_eq!(sorted_neighbors(&lattice, i), expected);
+        }
+    }
+
+    #[test]
+    fn video_neighbourhood() {
+        // 4x4 frames over 3 time steps, 4-connected in space plus previous/next frame
+        let stencil = Stencil::new(3, vec![vec![1, 0, 0], vec![0, 1, 0], vec![0, 0, 1]]).unwrap();
+        let lattice: Lattice<()> = Lattice::new(&[4, 4, 3], &stencil).unwrap();
+        let centre = lattice.index(&[1, 1, 1]);
+        assert_eq!(lattice.neighbors(centre).len(), 6);
+        assert_eq!(lattice.neighbors(lattice.index(&[0, 0, 0])).len(), 3);
+        assert_eq!(lattice.edges_along(&[0, 0, 1]).len(), 32);
+        assert_eq!(lattice.into_graph().maximal_cliques().len(), 3 * 24 + 32);
+    }
+
+    #[test]
+    fn four_dimensional_moore() {
+        let lattice: Lattice<()> = Lattice::new(&[3, 3, 3, 3], &Stencil::moore(4)).unwrap();
+        assert_eq!(lattice.neighbors(lattice.index(&[1, 1, 1, 1])).len(), 80);
+        assert_eq!(lattice.neighbors(0).len(), 15);
+        for i in 0..lattice.num_nodes() {
+            for &j in lattice.neighbors(i) {
+                assert!(lattice.neighbors(j).contains(&i));
+            }
+        }
+    }
+
+    #[test]
+    fn one_sided_stencils_connect_both_ways() {
+        let backward: Lattice<()> = Lattice::new(&[3, 3], &Stencil::new(2, vec![vec![-1, 0]]).unwrap()).unwrap();
+        assert_eq!(sorted_neighbors(&backward, backward.index(&[1, 1])), vec![3, 5]);
+        assert_eq!(sorted_neighbors(&backward, 0), vec![1]);
+        assert_eq!(backward.edges_along(&[-1, 0]).len(), 6);
+
+        let diagonal: Lattice<()> = Lattice::new(&[3, 3], &Stencil::new(2, vec![vec![1, -1]]).unwrap()).unwrap();
+        assert_eq!(sorted_neighbors(&diagonal, diagonal.index(&[1, 1])), vec![2, 6]);
+        assert_eq!(sorted_neighbors(&diagonal, diagonal.index(&[2, 0])), vec![4]);
+        assert!(diagonal.neighbors(0).is_empty());
+    }
+}
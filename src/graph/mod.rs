@@ -1,8 +1,14 @@
 mod grid;
+mod grid3d;
+mod hex;
+mod lattice;
 mod clique;
 mod node;
 
-pub use crate::graph::grid::Grid2D;
+pub use crate::graph::grid::{Boundary, Grid2D, GridLayout};
+pub use crate::graph::grid3d::{Connectivity3D, Eighteen, Grid3D, Six, TwentySix, VolumeLayout};
+pub use crate::graph::hex::{HexGrid, HexLayout, HexOffset};
+pub use crate::graph::lattice::{Lattice, LatticeLayout, Stencil};
 pub use crate::graph::clique::*;
 pub use crate::graph::node::*;
 
@@ -12,12 +18,68 @@ pub trait Neighborhood {
     fn num_nodes(&self) -> usize;
 }
 
+/// Geometry of a regular lattice, mapping node indices back to positions
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Layout {
+    Grid(GridLayout),
+    Volume(VolumeLayout),
+    Lattice(LatticeLayout),
+    Hex(HexLayout),
+}
+
+impl Layout {
+    pub fn as_grid(&self) -> Option<&GridLayout> {
+        match self {
+            Layout::Grid(layout) => Some(layout),
+            _ => None,
+        }
+    }
+
+    pub fn as_volume(&self) -> Option<&VolumeLayout> {
+        match self {
+            Layout::Volume(layout) => Some(layout),
+            _ => None,
+        }
+    }
+
+    pub fn as_lattice(&self) -> Option<&LatticeLayout> {
+        match self {
+            Layout::Lattice(layout) => Some(layout),
+            _ => None,
+        }
+    }
+
+    pub fn as_hex(&self) -> Option<&HexLayout> {
+        match self {
+            Layout::Hex(layout) => Some(layout),
+            _ => None,
+        }
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        match self {
+            Layout::Grid(layout) => layout.num_nodes(),
+            Layout::Volume(layout) => layout.num_nodes(),
+            Layout::Lattice(layout) => layout.num_nodes(),
+            Layout::Hex(layout) => layout.num_nodes(),
+        }
+    }
+}
+
+/// A lattice that can hand the MRF builder its graph, with cliques detected, and its layout
+pub trait LayoutGraph<T> {
+    fn into_layout_graph(self) -> (Layout, Graph<T>);
+}
+
 /// Convenient for constructing grids
 pub trait Connectivity {
     fn offsets(&self) -> &[(isize, isize)];
 }
 pub struct Four;
 pub struct Eight;
+/// Triangular lattice on sheared square coordinates: the four axis neighbours
+/// plus one diagonal, (1, 1) and (-1, -1). Every unit triangle is a 3-clique.
+pub struct Triangular;
 
 impl Connectivity for Four {
     fn offsets(&self) -> &'static [(isize, isize)] {
@@ -35,6 +97,16 @@ impl Connectivity for Eight {
     }
 }
 
+impl Connectivity for Triangular {
+    fn offsets(&self) -> &'static [(isize, isize)] {
+        &[
+            (-1, -1), (0, -1),
+            (-1,  0),          (1, 0),
+                      (0,  1), (1, 1),
+        ]
+    }
+}
+
 pub struct Graph<T> {
     nodes: Vec<Node<T>>,
     maximal_cliques: Vec<Clique>,
@@ -42,8 +114,12 @@ pub struct Graph<T> {
 }
 
 impl<T> Graph<T> {
-    /// Generates all sub-cliques of a given size from maximal cliques
+    /// Generates all sub-cliques of a given size from maximal cliques.
+    /// Order 1 yields every node, including isolated ones.
     pub fn cliques_of_order(&self, order: usize) -> Vec<Clique> {
+        if order == 1 {
+            return (0..self.nodes.len()).map(|i| Clique::new(vec![i])).collect();
+        }
         let mut result = Vec::new();
         let mut seen = std::collections::HashSet::<Vec<usize>>::new();
         for mc in &self.maximal_cliques {
@@ -104,9 +180,16 @@ impl<T: Default> Graph<T> {
 
     pub fn detect_cliques(&mut self) {
         self.maximal_cliques.clear();
-        let all: Vec<usize> = (0..self.nodes.len()).collect();
         let mut results = Vec::new();
-        Self::bron_kerbosch(&self.nodes, Vec::new(), all, Vec::new(), &mut results);
+        // Outer level in node order: each maximal clique is found once, from its
+        // lowest member, so the recursion only ever sees one neighbourhood.
+        // Keeps large lattices (e.g. volumes) linear in the node count.
+        for v in 0..self.nodes.len() {
+            let neighbors = self.nodes[v].neighbors();
+            let p = neighbors.iter().copied().filter(|&u| u > v).collect();
+            let x = neighbors.iter().copied().filter(|&u| u < v).collect();
+            Self::bron_kerbosch(&self.nodes, vec![v], p, x, &mut results);
+        }
         self.maximal_cliques = results;
     
         // Precompute per-node lookup
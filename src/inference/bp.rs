@@ -0,0 +1,580 @@
+use crate::MRF;
+use crate::error::MrfError;
+use crate::inference::factors::{FactorTables, increment, log_sum_exp, normalize_log};
+
+/// Order in which factor messages are updated within an iteration
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Schedule {
+    /// Flooding: every factor message is computed from the previous iteration's messages
+    Synchronous,
+    /// Factors are visited in order, each using the freshest incoming messages
+    Sequential,
+}
+
+pub struct BpOptions {
+    pub max_iterations: usize,
+    /// Stop once no factor message changes by more than this (in log space)
+    pub tolerance: f64,
+    /// Weight kept from the previous message, in [0, 1)
+    pub damping: f64,
+    pub schedule: Schedule,
+}
+
+impl Default for BpOptions {
+    fn default() -> Self {
+        Self {
+            max_iterations: 100,
+            tolerance: 1e-6,
+            damping: 0.0,
+            schedule: Schedule::Sequential,
+        }
+    }
+}
+
+/// Result of max-product belief propagation
+pub struct MapResult {
+    /// Lowest-energy labelling decoded over all iterations, also written into the graph
+    pub labels: Vec<usize>,
+    pub energy: f64,
+    /// `MRF::energy()` of the labelling decoded after each iteration
+    pub energies: Vec<f64>,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+pub struct BpResult {
+    /// Per-node belief over labels
+    pub marginals: Vec<Vec<f64>>,
+    /// Bethe approximation of -ln Z; exact on trees
+    pub bethe_free_energy: f64,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+/// Loopy sum-product belief propagation over a discrete MRF.
+/// Treats the model as p(x) ∝ exp(-energy(x)), with one factor per clique instance,
+/// conditioned on any clamped nodes.
+pub struct BeliefPropagation {
+    num_labels: usize,
+    opts: BpOptions,
+}
+
+impl BeliefPropagation {
+    pub fn new(num_labels: usize, opts: BpOptions) -> Self {
+        Self { num_labels, opts }
+    }
+
+    pub fn num_labels(&self) -> usize {
+        self.num_labels
+    }
+
+    pub fn options(&self) -> &BpOptions {
+        &self.opts
+    }
+
+    pub fn run(&self, mrf: &MRF<usize>) -> Result<BpResult, MrfError> {
+        if !(0.0..1.0).contains(&self.opts.damping) {
+            return Err(MrfError::InvalidParameter { name: "damping", value: self.opts.damping });
+        }
+        let mut tables = FactorTables::new(mrf, self.num_labels)?;
+        tables.condition(mrf)?;
+        let mut messages = Messages::new(&tables, Mode::Sum);
+        let (iterations, converged) = iterate(&self.opts, &tables, &mut messages, |_| {});
+
+        let marginals: Vec<Vec<f64>> = (0..tables.num_nodes())
+            .map(|node| normalize_log(&messages.node_total(&tables, node)))
+            .collect();
+        let bethe_free_energy = messages.bethe_free_energy(&tables, &marginals);
+        Ok(BpResult { marginals, bethe_free_energy, iterations, converged })
+    }
+}
+
+/// Max-product belief propagation, i.e. min-sum in the energy domain.
+/// Decodes a MAP labelling from the max-marginals after every iteration.
+pub struct MaxProductBp {
+    num_labels: usize,
+    opts: BpOptions,
+}
+
+impl MaxProductBp {
+    pub fn new(num_labels: usize, opts: BpOptions) -> Self {
+        Self { num_labels, opts }
+    }
+
+    pub fn num_labels(&self) -> usize {
+        self.num_labels
+    }
+
+    pub fn options(&self) -> &BpOptions {
+        &self.opts
+    }
+
+    /// Runs min-sum and writes the lowest-energy decoded labelling into the graph
+    pub fn run(&self, mrf: &mut MRF<usize>) -> Result<MapResult, MrfError> {
+        if !(0.0..1.0).contains(&self.opts.damping) {
+            return Err(MrfError::InvalidParameter { name: "damping", value: self.opts.damping });
+        }
+        let mut tables = FactorTables::new(mrf, self.num_labels)?;
+        tables.condition(mrf)?;
+        let mut messages = Messages::new(&tables, Mode::Max);
+
+        let mut energies = Vec::new();
+        let mut best: Option<(f64, Vec<usize>)> = None;
+        let (iterations, converged) = iterate(&self.opts, &tables, &mut messages, |messages| {
+            let labels = messages.decode(&tables);
+            // Clamped nodes keep their label, whatever the decode says
+            mrf.set_labels(&labels).unwrap();
+            let labels = mrf.labels();
+            let energy = mrf.energy();
+            energies.push(energy);
+            if best.as_ref().is_none_or(|(e, _)| energy < *e) {
+                best = Some((energy, labels));
+            }
+        });
+
+        let labels = match best {
+            Some((_, labels)) => labels,
+            None => messages.decode(&tables),
+        };
+        mrf.set_labels(&labels)?;
+        let labels = mrf.labels();
+        let energy = mrf.energy();
+        Ok(MapResult { labels, energy, energies, iterations, converged })
+    }
+}
+
+/// Runs message passes until convergence or the iteration limit,
+/// calling `after_pass` after each one. Returns (iterations, converged).
+fn iterate(
+    opts: &BpOptions,
+    tables: &FactorTables,
+    messages: &mut Messages,
+    mut after_pass: impl FnMut(&Messages),
+) -> (usize, bool) {
+    let mut iterations = 0;
+    while iterations < opts.max_iterations {
+        iterations += 1;
+        let delta = match opts.schedule {
+            Schedule::Synchronous => messages.synchronous_pass(tables, opts.damping),
+            Schedule::Sequential => messages.sequential_pass(tables, opts.damping),
+        };
+        after_pass(messages);
+        if delta < opts.tolerance {
+            return (iterations, true);
+        }
+    }
+    (iterations, false)
+}
+
+/// How factor messages marginalize over the other members
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Sum,
+    Max,
+}
+
+/// Log-domain messages on every (factor, position) edge, each `num_labels` long
+struct Messages {
+    mode: Mode,
+    num_labels: usize,
+    /// First edge of each factor; edge of (f, pos) is offsets[f] + pos
+    offsets: Vec<usize>,
+    to_var: Vec<f64>,
+    to_factor: Vec<f64>,
+}
+
+impl Messages {
+    fn new(tables: &FactorTables, mode: Mode) -> Self {
+        let mut offsets = Vec::with_capacity(tables.factors.len());
+        let mut edges = 0;
+        for f in &tables.factors {
+            offsets.push(edges);
+            edges += f.members.len();
+        }
+        let n = edges * tables.num_labels;
+        Self { mode, num_labels: tables.num_labels, offsets, to_var: vec![0.0; n], to_factor: vec![0.0; n] }
+    }
+
+    /// Label with the highest belief at every node, lowest label on ties
+    fn decode(&self, tables: &FactorTables) -> Vec<usize> {
+        (0..tables.num_nodes())
+            .map(|node| {
+                let total = self.node_total(tables, node);
+                let mut best = 0;
+                for (l, &v) in total.iter().enumerate() {
+                    if v > total[best] {
+                        best = l;
+                    }
+                }
+                best
+            })
+            .collect()
+    }
+
+    fn edge(&self, factor: usize, pos: usize) -> std::ops::Range<usize> {
+        let start = (self.offsets[factor] + pos) * self.num_labels;
+        start..start + self.num_labels
+    }
+
+    /// Sum of all incoming factor messages at a node
+    fn node_total(&self, tables: &FactorTables, node: usize) -> Vec<f64> {
+        let mut total = vec![0.0; self.num_labels];
+        for &(f, pos) in &tables.node_factors[node] {
+            for (t, m) in total.iter_mut().zip(&self.to_var[self.edge(f, pos)]) {
+                *t += m;
+            }
+        }
+        total
+    }
+
+    /// Recomputes the variable -> factor messages into one factor
+    fn update_to_factor(&mut self, tables: &FactorTables, factor: usize) {
+        for (pos, &node) in tables.factors[factor].members.iter().enumerate() {
+            // Sum the other factors' messages rather than subtracting this one,
+            // which would give NaN for labels ruled out by a clamped node
+            let mut msg = vec![0.0; self.num_labels];
+            for &(f, p) in &tables.node_factors[node] {
+                if (f, p) != (factor, pos) {
+                    for (m, v) in msg.iter_mut().zip(&self.to_var[self.edge(f, p)]) {
+                        *m += v;
+                    }
+                }
+            }
+            let own = self.edge(factor, pos);
+            let z = log_sum_exp(&msg);
+            for (dst, m) in self.to_factor[own].iter_mut().zip(&msg) {
+                *dst = m - z;
+            }
+        }
+    }
+
+    /// Factor -> variable messages out of one factor, from the current variable -> factor messages
+    fn factor_messages(&self, tables: &FactorTables, factor: usize) -> Vec<Vec<f64>> {
+        let f = &tables.factors[factor];
+        let k = f.members.len();
+        let incoming: Vec<&[f64]> = (0..k).map(|pos| &self.to_factor[self.edge(factor, pos)]).collect();
+
+        // Online log-sum-exp (or max) per (position, label)
+        let mut max = vec![vec![f64::NEG_INFINITY; self.num_labels]; k];
+        let mut sum = vec![vec![0.0; self.num_labels]; k];
+        let mut labels = vec![0; k];
+        for &energy in &f.energies {
+            let total: f64 = -energy + labels.iter().enumerate().map(|(pos, &l)| incoming[pos][l]).sum::<f64>();
+            for pos in 0..k {
+                let l = labels[pos];
+                let v = total - incoming[pos][l];
+                if v > max[pos][l] {
+                    sum[pos][l] = sum[pos][l] * (max[pos][l] - v).exp() + 1.0;
+                    max[pos][l] = v;
+                } else if self.mode == Mode::Sum && v > f64::NEG_INFINITY {
+                    sum[pos][l] += (v - max[pos][l]).exp();
+                }
+            }
+            increment(&mut labels, self.num_labels);
+        }
+
+        max.into_iter().zip(sum)
+            .map(|(max, sum)| {
+                let msg: Vec<f64> = match self.mode {
+                    Mode::Sum => max.iter().zip(&sum).map(|(m, s)| m + s.ln()).collect(),
+                    Mode::Max => max,
+                };
+                let z = log_sum_exp(&msg);
+                msg.into_iter().map(|m| m - z).collect()
+            })
+            .collect()
+    }
+
+    /// Writes damped factor messages, returning the largest change
+    fn store_factor_messages(&mut self, factor: usize, new: Vec<Vec<f64>>, damping: f64) -> f64 {
+        let mut delta: f64 = 0.0;
+        for (pos, msg) in new.into_iter().enumerate() {
+            let range = self.edge(factor, pos);
+            let mut damped: Vec<f64> = self.to_var[range.clone()].iter().zip(&msg)
+                // Skip the blend when undamped: 0 * -inf is NaN
+                .map(|(old, new)| if damping == 0.0 { *new } else { damping * old + (1.0 - damping) * new })
+                .collect();
+            let z = log_sum_exp(&damped);
+            for v in damped.iter_mut() {
+                *v -= z;
+            }
+            for (old, new) in self.to_var[range].iter_mut().zip(damped) {
+                delta = delta.max((new - *old).abs());
+                *old = new;
+            }
+        }
+        delta
+    }
+
+    fn synchronous_pass(&mut self, tables: &FactorTables, damping: f64) -> f64 {
+        for f in 0..tables.factors.len() {
+            self.update_to_factor(tables, f);
+        }
+        let new: Vec<Vec<Vec<f64>>> = (0..tables.factors.len())
+            .map(|f| self.factor_messages(tables, f))
+            .collect();
+        let mut delta: f64 = 0.0;
+        for (f, msgs) in new.into_iter().enumerate() {
+            delta = delta.max(self.store_factor_messages(f, msgs, damping));
+        }
+        delta
+    }
+
+    fn sequential_pass(&mut self, tables: &FactorTables, damping: f64) -> f64 {
+        let mut delta: f64 = 0.0;
+        for f in 0..tables.factors.len() {
+            self.update_to_factor(tables, f);
+            let msgs = self.factor_messages(tables, f);
+            delta = delta.max(self.store_factor_messages(f, msgs, damping));
+        }
+        delta
+    }
+
+    /// F = Σ_f Σ b_f (E_f + ln b_f) - Σ_i (d_i - 1) Σ b_i ln b_i
+    fn bethe_free_energy(&mut self, tables: &FactorTables, marginals: &[Vec<f64>]) -> f64 {
+        let mut free_energy = 0.0;
+        for (fi, f) in tables.factors.iter().enumerate() {
+            self.update_to_factor(tables, fi);
+            let mut labels = vec![0; f.members.len()];
+            let log_beliefs: Vec<f64> = f.energies.iter()
+                .map(|&energy| {
+                    let v = -energy + labels.iter().enumerate()
+                        .map(|(pos, &l)| self.to_factor[self.edge(fi, pos)][l])
+                        .sum::<f64>();
+                    increment(&mut labels, self.num_labels);
+                    v
+                })
+                .collect();
+            let beliefs = normalize_log(&log_beliefs);
+            for (b, &energy) in beliefs.iter().zip(&f.energies) {
+                if *b > 0.0 {
+                    free_energy += b * (energy + b.ln());
+                }
+            }
+        }
+        for (node, belief) in marginals.iter().enumerate() {
+            let degree = tables.node_factors[node].len() as f64;
+            let neg_entropy: f64 = belief.iter().filter(|&&b| b > 0.0).map(|b| b * b.ln()).sum();
+            free_energy -= (degree - 1.0) * neg_entropy;
+        }
+        free_energy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Graph;
+    use crate::potentials::{TablePotential, UnaryPotential};
+
+    fn chain(n: usize) -> Graph<usize> {
+        let mut g = Graph::new(n);
+        for i in 0..n - 1 {
+            g.add_edge(i, i + 1);
+        }
+        g.detect_cliques();
+        g
+    }
+
+    fn chain_mrf(n: usize) -> MRF<usize> {
+        let pairwise = TablePotential::new(vec![0.0, 0.8, 1.5, 0.8, 0.0, 0.4, 1.5, 0.4, 0.0], 3, 2).unwrap();
+        let unary = UnaryPotential::new(
+            (0..n).map(|i| vec![0.1 * i as f64, 0.5, 1.0 - 0.2 * i as f64]).collect(),
+        ).unwrap();
+        MRF::<usize>::builder().graph(chain(n)).potential(pairwise).potential(unary).build().unwrap()
+    }
+
+    /// Exact marginals and -ln Z by enumeration
+    fn brute_force(mrf: &mut MRF<usize>, num_labels: usize) -> (Vec<Vec<f64>>, f64) {
+        let n = mrf.num_nodes();
+        let mut labels = vec![0; n];
+        let mut log_weights = Vec::new();
+        let mut assignments = Vec::new();
+        for _ in 0..num_labels.pow(n as u32) {
+            for (i, &l) in labels.iter().enumerate() {
+                mrf.graph_mut().get_node_mut(i).set_state(l);
+            }
+            log_weights.push(-mrf.energy());
+            assignments.push(labels.clone());
+            increment(&mut labels, num_labels);
+        }
+        let log_z = log_sum_exp(&log_weights);
+        let mut marginals = vec![vec![0.0; num_labels]; n];
+        for (w, a) in log_weights.iter().zip(&assignments) {
+            for (i, &l) in a.iter().enumerate() {
+                marginals[i][l] += (w - log_z).exp();
+            }
+        }
+        (marginals, -log_z)
+    }
+
+    #[test]
+    fn exact_on_chain() {
+        let mut mrf = chain_mrf(5);
+        for schedule in [Schedule::Synchronous, Schedule::Sequential] {
+            let bp = BeliefPropagation::new(3, BpOptions { schedule, ..Default::default() });
+            let result = bp.run(&mrf).unwrap();
+            assert!(result.converged);
+            let (exact, free_energy) = brute_force(&mut mrf, 3);
+            for (b, e) in result.marginals.iter().zip(&exact) {
+                for (x, y) in b.iter().zip(e) {
+                    assert!((x - y).abs() < 1e-6, "{x} vs {y}");
+                }
+            }
+            assert!((result.bethe_free_energy - free_energy).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn damped_loopy_grid_converges() {
+        let mut g = Graph::new(9);
+        for r in 0..3 {
+            for c in 0..3 {
+                let i = r * 3 + c;
+                if c + 1 < 3 { g.add_edge(i, i + 1); }
+                if r + 1 < 3 { g.add_edge(i, i + 3); }
+            }
+        }
+        g.detect_cliques();
+        let potts = TablePotential::new(vec![0.0, 0.7, 0.7, 0.0], 2, 2).unwrap();
+        let field = UnaryPotential::new((0..9).map(|i| vec![0.0, 0.1 * i as f64]).collect()).unwrap();
+        let mut mrf = MRF::<usize>::builder().graph(g).potential(potts).potential(field).build().unwrap();
+
+        let bp = BeliefPropagation::new(2, BpOptions { damping: 0.5, ..Default::default() });
+        let result = bp.run(&mrf).unwrap();
+        assert!(result.converged);
+        let (exact, free_energy) = brute_force(&mut mrf, 2);
+        for (b, e) in result.marginals.iter().zip(&exact) {
+            assert!((b.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+            assert!((b[0] - e[0]).abs() < 0.05);
+        }
+        assert!((result.bethe_free_energy - free_energy).abs() < 0.1);
+    }
+
+    /// Lowest energy over all labellings
+    fn brute_force_map(mrf: &mut MRF<usize>, num_labels: usize) -> f64 {
+        let n = mrf.num_nodes();
+        let mut labels = vec![0; n];
+        let mut best = f64::INFINITY;
+        for _ in 0..num_labels.pow(n as u32) {
+            for (i, &l) in labels.iter().enumerate() {
+                mrf.graph_mut().get_node_mut(i).set_state(l);
+            }
+            best = best.min(mrf.energy());
+            increment(&mut labels, num_labels);
+        }
+        best
+    }
+
+    #[test]
+    fn max_product_exact_on_chain() {
+        let mut mrf = chain_mrf(6);
+        let optimum = brute_force_map(&mut mrf, 3);
+        let bp = MaxProductBp::new(3, BpOptions::default());
+        let result = bp.run(&mut mrf).unwrap();
+        assert!(result.converged);
+        assert!((result.energy - optimum).abs() < 1e-9);
+        assert_eq!(result.energies.len(), result.iterations);
+
+        // Labelling is written into the graph
+        for (node, &label) in result.labels.iter().enumerate() {
+            assert_eq!(*mrf.graph().get_node(node).state(), label);
+        }
+        assert!((mrf.energy() - optimum).abs() < 1e-9);
+    }
+
+    #[test]
+    fn max_product_denoises_grid() {
+        // 4x4 two-label image: left half 0, right half 1, with strong smoothing
+        let mut g = Graph::new(16);
+        for r in 0..4 {
+            for c in 0..4 {
+                let i = r * 4 + c;
+                if c + 1 < 4 { g.add_edge(i, i + 1); }
+                if r + 1 < 4 { g.add_edge(i, i + 4); }
+            }
+        }
+        g.detect_cliques();
+        let mut observed: Vec<usize> = (0..16).map(|i| usize::from(i % 4 >= 2)).collect();
+        observed[5] = 1; // noise
+        let unary = UnaryPotential::new(
+            observed.iter().map(|&o| if o == 0 { vec![0.0, 1.0] } else { vec![1.0, 0.0] }).collect(),
+        ).unwrap();
+        let potts = TablePotential::new(vec![0.0, 0.6, 0.6, 0.0], 2, 2).unwrap();
+        let mut mrf = MRF::<usize>::builder().graph(g).potential(potts).potential(unary).build().unwrap();
+
+        let bp = MaxProductBp::new(2, BpOptions { damping: 0.3, ..Default::default() });
+        let result = bp.run(&mut mrf).unwrap();
+        let expected: Vec<usize> = (0..16).map(|i| usize::from(i % 4 >= 2)).collect();
+        assert_eq!(result.labels, expected);
+    }
+
+    #[test]
+    fn invalid_damping_fails() {
+        let mrf = chain_mrf(3);
+        let bp = BeliefPropagation::new(3, BpOptions { damping: 1.0, ..Default::default() });
+        assert!(matches!(bp.run(&mrf), Err(MrfError::InvalidParameter { name: "damping", .. })));
+    }
+
+    #[test]
+    fn conditions_on_clamped_nodes() {
+        let mut mrf = chain_mrf(5);
+        mrf.clamp(2, 2).unwrap();
+        mrf.clamp(4, 0).unwrap();
+        let exact = crate::inference::ExactInference::new(3, 1 << 10).run(&mrf).unwrap();
+        let result = BeliefPropagation::new(3, BpOptions::default()).run(&mrf).unwrap();
+        assert!(result.converged);
+        for (b, e) in result.marginals.iter().zip(&exact.marginals) {
+            for (x, y) in b.iter().zip(e) {
+                assert!((x - y).abs() < 1e-6, "{x} vs {y}");
+            }
+        }
+        assert!((result.bethe_free_energy + exact.log_partition).abs() < 1e-6);
+
+        let map = MaxProductBp::new(3, BpOptions::default()).run(&mut mrf).unwrap();
+        assert_eq!(map.labels, exact.map_labels);
+        assert!((map.energy - exact.map_energy).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rejects_mismatched_label_count() {
+        let binary = TablePotential::new(vec![0.0, 1.0, 1.0, 0.0], 2, 2).unwrap();
+        let mrf = MRF::<usize>::builder().graph(chain(3)).potential(binary).build().unwrap();
+        let result = BeliefPropagation::new(3, BpOptions::default()).run(&mrf);
+        assert!(matches!(result, Err(MrfError::DimensionMismatch { expected: 3, got: 2 })));
+    }
+
+    #[test]
+    fn isolated_clamped_node_has_point_mass() {
+        let mut graph = Graph::new(3);
+        graph.add_edge(0, 1);
+        graph.detect_cliques();
+        let binary = TablePotential::new(vec![0.0, 0.7, 0.7, 0.0], 2, 2).unwrap();
+        let mut mrf = MRF::<usize>::builder().graph(graph).potential(binary).build().unwrap();
+        mrf.clamp(2, 1).unwrap();
+        let exact = crate::inference::ExactInference::new(2, 1 << 10).run(&mrf).unwrap();
+        let result = BeliefPropagation::new(2, BpOptions::default()).run(&mrf).unwrap();
+        assert_eq!(result.marginals[2], vec![0.0, 1.0]);
+        for (b, e) in result.marginals.iter().zip(&exact.marginals) {
+            for (x, y) in b.iter().zip(e) {
+                assert!((x - y).abs() < 1e-9, "{x} vs {y}");
+            }
+        }
+        assert!((result.bethe_free_energy + exact.log_partition).abs() < 1e-9);
+    }
+
+    #[test]
+    fn map_keeps_isolated_clamped_node() {
+        // Node 3 is in no factor, so nothing in the messages pins its label
+        let mut graph = Graph::new(4);
+        graph.add_edge(0, 1);
+        graph.add_edge(1, 2);
+        graph.detect_cliques();
+        let pairwise = TablePotential::new(vec![0.0, 0.8, 1.5, 0.8, 0.0, 0.4, 1.5, 0.4, 0.0], 3, 2).unwrap();
+        let mut mrf = MRF::<usize>::builder().graph(graph).potential(pairwise).build().unwrap();
+        mrf.clamp(3, 2).unwrap();
+        let map = MaxProductBp::new(3, BpOptions::default()).run(&mut mrf).unwrap();
+        assert_eq!(map.labels[3], 2);
+        assert_eq!(*mrf.graph().get_node(3).state(), 2);
+    }
+}
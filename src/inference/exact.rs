@@ -0,0 +1,207 @@
+use crate::MRF;
+use crate::error::MrfError;
+use crate::inference::factors::{FactorTables, clamped_labels};
+
+pub struct ExactResult {
+    /// ln Z for p(x) = exp(-energy(x)) / Z, summed over labellings that agree with clamped nodes
+    pub log_partition: f64,
+    /// Per-node marginal over labels
+    pub marginals: Vec<Vec<f64>>,
+    /// Per potential, per clique instance (as in `MRF::potential_cliques`): the joint
+    /// marginal of the clique's members, in `TablePotential` index order
+    pub clique_marginals: Vec<Vec<Vec<f64>>>,
+    /// Lowest-energy labelling, lowest in enumeration order on ties
+    pub map_labels: Vec<usize>,
+    pub map_energy: f64,
+}
+
+impl ExactResult {
+    pub fn clique_marginal(&self, potential: usize, instance: usize) -> &[f64] {
+        &self.clique_marginals[potential][instance]
+    }
+}
+
+/// Exact inference by enumerating every labelling of the free nodes, with
+/// clamped nodes held at their labels.
+/// Meant as a ground-truth oracle for small models in tests.
+pub struct ExactInference {
+    num_labels: usize,
+    max_states: u64,
+}
+
+impl ExactInference {
+    /// Refuses models with more than `max_states` labellings of their free nodes
+    pub fn new(num_labels: usize, max_states: u64) -> Self {
+        Self { num_labels, max_states }
+    }
+
+    pub fn num_labels(&self) -> usize {
+        self.num_labels
+    }
+
+    pub fn max_states(&self) -> u64 {
+        self.max_states
+    }
+
+    pub fn run(&self, mrf: &MRF<usize>) -> Result<ExactResult, MrfError> {
+        let num_nodes = mrf.num_nodes();
+        let free = mrf.free_nodes();
+        let states = u32::try_from(free.len()).ok()
+            .and_then(|n| (self.num_labels as u64).checked_pow(n))
+            .filter(|&s| s <= self.max_states)
+            .ok_or(MrfError::TooManyStates {
+                num_labels: self.num_labels,
+                num_nodes: free.len(),
+                limit: self.max_states,
+            })?;
+        let tables = FactorTables::new(mrf, self.num_labels)?;
+        let l = self.num_labels;
+        let evidence = clamped_labels(mrf, l)?;
+
+        // Accumulators hold sum(exp(-E - shift)); rescaled whenever the shift grows
+        let mut shift = f64::NEG_INFINITY;
+        let mut total = 0.0;
+        let mut marginals = vec![vec![0.0; l]; num_nodes];
+        let mut factor_marginals: Vec<Vec<f64>> = tables.factors.iter()
+            .map(|f| vec![0.0; f.energies.len()])
+            .collect();
+        let mut map_labels = vec![0; num_nodes];
+        let mut map_energy = f64::INFINITY;
+
+        let mut labels: Vec<usize> = evidence.iter().map(|e| e.unwrap_or(0)).collect();
+        for _ in 0..states {
+            let energy = tables.energy(&labels);
+            if energy < map_energy {
+                map_energy = energy;
+                map_labels.copy_from_slice(&labels);
+            }
+
+            let log_weight = -energy;
+            if log_weight > shift {
+                let scale = (shift - log_weight).exp();
+                total *= scale;
+                marginals.iter_mut().flatten().for_each(|m| *m *= scale);
+                factor_marginals.iter_mut().flatten().for_each(|m| *m *= scale);
+                shift = log_weight;
+            }
+            let w = (log_weight - shift).exp();
+            total += w;
+            for (node, &label) in labels.iter().enumerate() {
+                marginals[node][label] += w;
+            }
+            for (f, m) in factor_marginals.iter_mut().enumerate() {
+                m[tables.index(f, &labels)] += w;
+            }
+            // Advance the free nodes in table order, last one fastest
+            for &node in free.iter().rev() {
+                labels[node] += 1;
+                if labels[node] < l {
+                    break;
+                }
+                labels[node] = 0;
+            }
+        }
+
+        marginals.iter_mut().flatten().for_each(|m| *m /= total);
+        factor_marginals.iter_mut().flatten().for_each(|m| *m /= total);
+        let mut clique_marginals: Vec<Vec<Vec<f64>>> = (0..mrf.potentials().len())
+            .map(|pi| Vec::with_capacity(mrf.potential_cliques(pi).len()))
+            .collect();
+        for (m, &(pi, _)) in factor_marginals.into_iter().zip(&tables.owners) {
+            clique_marginals[pi].push(m);
+        }
+
+        Ok(ExactResult {
+            log_partition: shift + total.ln(),
+            marginals,
+            clique_marginals,
+            map_labels,
+            map_energy,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::Graph;
+    use crate::potentials::{TablePotential, UnaryPotential};
+    use crate::samplers::{ConstantAnnealer, DiscreteProposal, GibbsSampler, RunOptions};
+
+    fn pair_mrf() -> MRF<usize> {
+        let mut g = Graph::new(2);
+        g.add_edge(0, 1);
+        g.detect_cliques();
+        let pairwise = TablePotential::new(vec![0.0, 1.0, 2.0, 0.5], 2, 2).unwrap();
+        let unary = UnaryPotential::new(vec![vec![0.0, 0.3], vec![0.7, 0.0]]).unwrap();
+        MRF::<usize>::builder().graph(g).potential(pairwise).potential(unary).build().unwrap()
+    }
+
+    #[test]
+    fn two_node_model_by_hand() {
+        let result = ExactInference::new(2, 16).run(&pair_mrf()).unwrap();
+        // Energies of (x0, x1): pairwise + unary
+        let e: [f64; 4] = [0.0 + 0.7, 1.0 + 0.0, 2.0 + 0.3 + 0.7, 0.5 + 0.3];
+        let z: f64 = e.iter().map(|e| (-e).exp()).sum();
+        assert!((result.log_partition - z.ln()).abs() < 1e-12);
+
+        let p: Vec<f64> = e.iter().map(|e| (-e).exp() / z).collect();
+        assert!((result.marginals[0][1] - (p[2] + p[3])).abs() < 1e-12);
+        assert!((result.marginals[1][1] - (p[1] + p[3])).abs() < 1e-12);
+        for (k, pk) in p.iter().enumerate() {
+            assert!((result.clique_marginal(0, 0)[k] - pk).abs() < 1e-12);
+        }
+        assert_eq!(result.map_labels, vec![0, 0]);
+        assert!((result.map_energy - 0.7).abs() < 1e-12);
+    }
+
+    #[test]
+    fn refuses_large_models() {
+        let result = ExactInference::new(2, 3).run(&pair_mrf());
+        assert!(matches!(result, Err(MrfError::TooManyStates { num_labels: 2, num_nodes: 2, limit: 3 })));
+    }
+
+    #[test]
+    fn validates_gibbs_marginals() {
+        // 4-cycle with a field on node 0
+        let mut g = Graph::new(4);
+        for i in 0..4 {
+            g.add_edge(i, (i + 1) % 4);
+        }
+        g.detect_cliques();
+        let potts = TablePotential::new(vec![0.0, 0.8, 0.8, 0.0], 2, 2).unwrap();
+        let field = UnaryPotential::new(vec![vec![0.0, 1.0], vec![0.0; 2], vec![0.0; 2], vec![0.0; 2]]).unwrap();
+        let mut mrf = MRF::<usize>::builder().graph(g).potential(potts).potential(field).build().unwrap();
+        let exact = ExactInference::new(2, 16).run(&mrf).unwrap();
+
+        let sweeps = 20_000;
+        let mut ones = [0usize; 4];
+        let sampler = GibbsSampler::new(sweeps, ConstantAnnealer::new(1.0));
+        sampler.run_with(&mut mrf, &DiscreteProposal::new(2), RunOptions { seed: Some(7) }, |i, m| {
+            if i > 0 {
+                for (node, count) in ones.iter_mut().enumerate() {
+                    *count += *m.graph().get_node(node).state();
+                }
+            }
+        }).unwrap();
+        for (node, &count) in ones.iter().enumerate() {
+            let empirical = count as f64 / sweeps as f64;
+            assert!((empirical - exact.marginals[node][1]).abs() < 0.02, "node {node}: {empirical}");
+        }
+    }
+
+    #[test]
+    fn conditions_on_clamped_nodes() {
+        let mut mrf = pair_mrf();
+        mrf.clamp(1, 1).unwrap();
+        let result = ExactInference::new(2, 2).run(&mrf).unwrap();
+        // Energies of (0, 1) and (1, 1)
+        let e: [f64; 2] = [1.0, 0.5 + 0.3];
+        let z: f64 = e.iter().map(|e| (-e).exp()).sum();
+        assert!((result.log_partition - z.ln()).abs() < 1e-12);
+        assert!((result.marginals[0][1] - (-e[1]).exp() / z).abs() < 1e-12);
+        assert_eq!(result.marginals[1], vec![0.0, 1.0]);
+        assert_eq!(result.map_labels, vec![1, 1]);
+        assert!((result.map_energy - 0.8).abs() < 1e-12);
+    }
+}
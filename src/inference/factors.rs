@@ -0,0 +1,146 @@
+use crate::MRF;
+use crate::error::MrfError;
+
+/// One clique instance of an MRF with its energy tabulated over every labelling.
+/// Index convention matches `TablePotential`: the first member is most significant.
+pub(crate) struct FactorTable {
+    pub members: Vec<usize>,
+    pub energies: Vec<f64>,
+}
+
+/// Every (potential, clique) pair of a discrete MRF as an explicit factor table,
+/// plus a node -> (factor, position) index.
+pub(crate) struct FactorTables {
+    pub num_labels: usize,
+    pub factors: Vec<FactorTable>,
+    /// (potential, instance) each factor was built from; evidence factors added
+    /// by `condition` come last and have no owner
+    pub owners: Vec<(usize, usize)>,
+    pub node_factors: Vec<Vec<(usize, usize)>>,
+}
+
+impl FactorTables {
+    /// Tabulates every factor over `num_labels` labels per node. Potentials with a
+    /// fixed label space must have exactly that many labels.
+    pub fn new(mrf: &MRF<usize>, num_labels: usize) -> Result<Self, MrfError> {
+        if num_labels == 0 {
+            return Err(MrfError::EmptyStateSpace);
+        }
+        let mut factors = Vec::new();
+        let mut owners = Vec::new();
+        for (pi, p) in mrf.potentials().iter().enumerate() {
+            if let Some(got) = p.num_labels().filter(|&n| n != num_labels) {
+                return Err(MrfError::DimensionMismatch { expected: num_labels, got });
+            }
+            for (ci, clique) in mrf.potential_cliques(pi).iter().enumerate() {
+                let members = clique.members().to_vec();
+                let mut labels = vec![0; members.len()];
+                let size = num_labels.pow(members.len() as u32);
+                let mut energies = Vec::with_capacity(size);
+                for _ in 0..size {
+                    energies.push(p.score_at(&members, &labels));
+                    increment(&mut labels, num_labels);
+                }
+                factors.push(FactorTable { members, energies });
+                owners.push((pi, ci));
+            }
+        }
+
+        let mut node_factors = vec![Vec::new(); mrf.num_nodes()];
+        for (fi, f) in factors.iter().enumerate() {
+            for (pos, &node) in f.members.iter().enumerate() {
+                node_factors[node].push((fi, pos));
+            }
+        }
+        Ok(Self { num_labels, factors, owners, node_factors })
+    }
+
+    /// Conditions on the MRF's clamped nodes: table entries that give a clamped
+    /// node any other label get infinite energy, i.e. zero probability. Clamped
+    /// nodes in no factor get a unary evidence factor doing the same.
+    pub fn condition(&mut self, mrf: &MRF<usize>) -> Result<(), MrfError> {
+        let num_labels = self.num_labels;
+        let evidence = clamped_labels(mrf, num_labels)?;
+        for f in &mut self.factors {
+            let fixed: Vec<(usize, usize)> = f.members.iter().enumerate()
+                .filter_map(|(pos, &m)| evidence[m].map(|l| (pos, l)))
+                .collect();
+            if fixed.is_empty() {
+                continue;
+            }
+            let mut labels = vec![0; f.members.len()];
+            for e in f.energies.iter_mut() {
+                if fixed.iter().any(|&(pos, l)| labels[pos] != l) {
+                    *e = f64::INFINITY;
+                }
+                increment(&mut labels, num_labels);
+            }
+        }
+        for (node, label) in evidence.into_iter().enumerate() {
+            if let Some(label) = label.filter(|_| self.node_factors[node].is_empty()) {
+                let energies = (0..num_labels).map(|l| if l == label { 0.0 } else { f64::INFINITY }).collect();
+                self.node_factors[node].push((self.factors.len(), 0));
+                self.factors.push(FactorTable { members: vec![node], energies });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.node_factors.len()
+    }
+
+    /// Flat table index of a factor's members under a full labelling
+    pub fn index(&self, factor: usize, labels: &[usize]) -> usize {
+        self.factors[factor].members.iter().fold(0, |idx, &m| idx * self.num_labels + labels[m])
+    }
+
+    /// Total energy of a full labelling
+    pub fn energy(&self, labels: &[usize]) -> f64 {
+        (0..self.factors.len())
+            .map(|f| self.factors[f].energies[self.index(f, labels)])
+            .sum()
+    }
+}
+
+/// Label of every clamped node, checked against the label count
+pub(crate) fn clamped_labels(mrf: &MRF<usize>, num_labels: usize) -> Result<Vec<Option<usize>>, MrfError> {
+    (0..mrf.num_nodes())
+        .map(|node| {
+            if !mrf.is_clamped(node) {
+                return Ok(None);
+            }
+            let label = *mrf.graph().get_node(node).state();
+            if label >= num_labels {
+                return Err(MrfError::LabelOutOfRange { node, label, num_labels });
+            }
+            Ok(Some(label))
+        })
+        .collect()
+}
+
+/// Advances a labelling to the next one in table order (last position fastest)
+pub(crate) fn increment(labels: &mut [usize], num_labels: usize) {
+    for l in labels.iter_mut().rev() {
+        *l += 1;
+        if *l < num_labels {
+            return;
+        }
+        *l = 0;
+    }
+}
+
+/// ln(sum(exp(values)))
+pub(crate) fn log_sum_exp(values: &[f64]) -> f64 {
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if max == f64::NEG_INFINITY {
+        return max;
+    }
+    max + values.iter().map(|&v| (v - max).exp()).sum::<f64>().ln()
+}
+
+/// Normalizes log-weights into probabilities
+pub(crate) fn normalize_log(values: &[f64]) -> Vec<f64> {
+    let z = log_sum_exp(values);
+    values.iter().map(|&v| (v - z).exp()).collect()
+}
@@ -0,0 +1,395 @@
+use crate::MRF;
+use crate::error::MrfError;
+use crate::inference::factors::FactorTables;
+use crate::inference::maxflow::MaxFlow;
+
+const TOLERANCE: f64 = 1e-9;
+
+/// Result of a move-making optimizer
+pub struct CutResult {
+    /// Final labelling, also written into the graph
+    pub labels: Vec<usize>,
+    pub energy: f64,
+    /// Energy after each cycle over all moves
+    pub energies: Vec<f64>,
+    pub cycles: usize,
+}
+
+/// Alpha-expansion (Boykov, Veksler & Zabih): each move lets any node switch to
+/// label alpha, solved exactly with a min-cut. Requires unary and metric pairwise terms.
+pub struct AlphaExpansion {
+    num_labels: usize,
+    max_cycles: usize,
+}
+
+impl AlphaExpansion {
+    pub fn new(num_labels: usize, max_cycles: usize) -> Self {
+        Self { num_labels, max_cycles }
+    }
+
+    pub fn num_labels(&self) -> usize {
+        self.num_labels
+    }
+
+    pub fn max_cycles(&self) -> usize {
+        self.max_cycles
+    }
+
+    /// Improves the graph's current labelling until no expansion lowers the energy
+    pub fn run(&self, mrf: &mut MRF<usize>) -> Result<CutResult, MrfError> {
+        let problem = CutProblem::new(mrf, self.num_labels, Requirement::Metric)?;
+        let moves: Vec<(usize, Option<usize>)> = (0..self.num_labels).map(|a| (a, None)).collect();
+        problem.optimize(mrf, &moves, self.max_cycles)
+    }
+}
+
+/// Alpha-beta swap: each move lets nodes labelled alpha or beta exchange labels,
+/// solved exactly with a min-cut. Requires unary and semi-metric pairwise terms.
+pub struct AlphaBetaSwap {
+    num_labels: usize,
+    max_cycles: usize,
+}
+
+impl AlphaBetaSwap {
+    pub fn new(num_labels: usize, max_cycles: usize) -> Self {
+        Self { num_labels, max_cycles }
+    }
+
+    pub fn num_labels(&self) -> usize {
+        self.num_labels
+    }
+
+    pub fn max_cycles(&self) -> usize {
+        self.max_cycles
+    }
+
+    /// Improves the graph's current labelling until no swap lowers the energy
+    pub fn run(&self, mrf: &mut MRF<usize>) -> Result<CutResult, MrfError> {
+        let problem = CutProblem::new(mrf, self.num_labels, Requirement::SemiMetric)?;
+        let moves: Vec<(usize, Option<usize>)> = (0..self.num_labels)
+            .flat_map(|a| (a + 1..self.num_labels).map(move |b| (a, Some(b))))
+            .collect();
+        problem.optimize(mrf, &moves, self.max_cycles)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Requirement {
+    Metric,
+    SemiMetric,
+}
+
+impl Requirement {
+    fn name(self) -> &'static str {
+        match self {
+            Requirement::Metric => "metric",
+            Requirement::SemiMetric => "semi-metric",
+        }
+    }
+}
+
+/// A pairwise MRF split into per-node unary costs and pairwise factors
+struct CutProblem {
+    num_labels: usize,
+    tables: FactorTables,
+    clamped: Vec<bool>,
+    unary: Vec<Vec<f64>>,
+    pairwise: Vec<usize>,
+}
+
+impl CutProblem {
+    fn new(mrf: &MRF<usize>, num_labels: usize, requirement: Requirement) -> Result<Self, MrfError> {
+        let tables = FactorTables::new(mrf, num_labels)?;
+        for node in 0..mrf.num_nodes() {
+            let label = *mrf.graph().get_node(node).state();
+            if label >= num_labels {
+                return Err(MrfError::LabelOutOfRange { node, label, num_labels });
+            }
+        }
+
+        let mut unary = vec![vec![0.0; num_labels]; mrf.num_nodes()];
+        let mut pairwise = Vec::new();
+        for (fi, f) in tables.factors.iter().enumerate() {
+            match f.members.len() {
+                1 => {
+                    for (u, e) in unary[f.members[0]].iter_mut().zip(&f.energies) {
+                        *u += e;
+                    }
+                }
+                2 => {
+                    check_metric(&f.energies, num_labels, requirement, tables.owners[fi].0)?;
+                    pairwise.push(fi);
+                }
+                order => return Err(MrfError::UnsupportedOrder { order, max: 2 }),
+            }
+        }
+        let clamped = (0..mrf.num_nodes()).map(|i| mrf.is_clamped(i)).collect();
+        Ok(Self { num_labels, tables, clamped, unary, pairwise })
+    }
+
+    fn pair(&self, factor: usize, a: usize, b: usize) -> f64 {
+        self.tables.factors[factor].energies[a * self.num_labels + b]
+    }
+
+    fn energy(&self, labels: &[usize]) -> f64 {
+        let unary: f64 = labels.iter().enumerate().map(|(i, &l)| self.unary[i][l]).sum();
+        let pairwise: f64 = self.pairwise.iter()
+            .map(|&f| {
+                let m = &self.tables.factors[f].members;
+                self.pair(f, labels[m[0]], labels[m[1]])
+            })
+            .sum();
+        unary + pairwise
+    }
+
+    /// Cycles through the moves until a full cycle brings no improvement
+    fn optimize(
+        &self,
+        mrf: &mut MRF<usize>,
+        moves: &[(usize, Option<usize>)],
+        max_cycles: usize,
+    ) -> Result<CutResult, MrfError> {
+        let mut labels: Vec<usize> = (0..mrf.num_nodes())
+            .map(|i| *mrf.graph().get_node(i).state())
+            .collect();
+        let mut energy = self.energy(&labels);
+        let mut energies = Vec::new();
+        let mut cycles = 0;
+
+        while cycles < max_cycles {
+            cycles += 1;
+            let mut improved = false;
+            for &(alpha, beta) in moves {
+                let candidate = self.best_move(&labels, alpha, beta);
+                let candidate_energy = self.energy(&candidate);
+                if candidate_energy < energy - TOLERANCE {
+                    labels = candidate;
+                    energy = candidate_energy;
+                    improved = true;
+                }
+            }
+            energies.push(energy);
+            if !improved {
+                break;
+            }
+        }
+
+        for (node, &label) in labels.iter().enumerate() {
+            mrf.graph_mut().get_node_mut(node).set_state(label);
+        }
+        Ok(CutResult { labels, energy, energies, cycles })
+    }
+
+    /// Optimal expansion (beta = None) or alpha-beta swap via one min-cut.
+    /// Each participating (free) node chooses between two labels: x = 0 keeps `first`,
+    /// x = 1 takes `second`. Source side of the cut is x = 0.
+    fn best_move(&self, labels: &[usize], alpha: usize, beta: Option<usize>) -> Vec<usize> {
+        let n = labels.len();
+        let choices: Vec<Option<(usize, usize)>> = labels.iter().zip(&self.clamped)
+            .map(|(&l, &clamped)| match beta {
+                _ if clamped => None,
+                None => Some((l, alpha)),
+                Some(beta) if l == alpha || l == beta => Some((alpha, beta)),
+                Some(_) => None,
+            })
+            .collect();
+
+        let (source, sink) = (n, n + 1);
+        let mut flow = MaxFlow::new(n + 2);
+        // Cost of x = 0 and x = 1 per node
+        let mut costs: Vec<(f64, f64)> = choices.iter().enumerate()
+            .map(|(i, c)| match c {
+                Some((first, second)) => (self.unary[i][*first], self.unary[i][*second]),
+                None => (0.0, 0.0),
+            })
+            .collect();
+
+        for &f in &self.pairwise {
+            let m = &self.tables.factors[f].members;
+            let (i, j) = (m[0], m[1]);
+            match (choices[i], choices[j]) {
+                (Some((i0, i1)), Some((j0, j1))) => {
+                    // E(x_i, x_j) = A + (C - A) x_i + (D - C) x_j + (B + C - A - D)(1 - x_i) x_j
+                    let a = self.pair(f, i0, j0);
+                    let b = self.pair(f, i0, j1);
+                    let c = self.pair(f, i1, j0);
+                    let d = self.pair(f, i1, j1);
+                    costs[i].0 += a;
+                    costs[i].1 += c;
+                    costs[j].1 += d - c;
+                    let coupling = b + c - a - d;
+                    debug_assert!(coupling >= -TOLERANCE);
+                    if coupling > 0.0 {
+                        flow.add_edge(i, j, coupling);
+                    }
+                }
+                (Some((i0, i1)), None) => {
+                    costs[i].0 += self.pair(f, i0, labels[j]);
+                    costs[i].1 += self.pair(f, i1, labels[j]);
+                }
+                (None, Some((j0, j1))) => {
+                    costs[j].0 += self.pair(f, labels[i], j0);
+                    costs[j].1 += self.pair(f, labels[i], j1);
+                }
+                (None, None) => {}
+            }
+        }
+
+        for (i, &(zero, one)) in costs.iter().enumerate() {
+            if choices[i].is_none() {
+                continue;
+            }
+            if one > zero {
+                flow.add_edge(source, i, one - zero);
+            } else if zero > one {
+                flow.add_edge(i, sink, zero - one);
+            }
+        }
+        flow.solve(source, sink);
+
+        labels.iter().enumerate()
+            .map(|(i, &l)| match choices[i] {
+                Some((first, second)) => if flow.on_source_side(i) { first } else { second },
+                None => l,
+            })
+            .collect()
+    }
+}
+
+/// Checks V(a, b) = V(b, a) >= 0 with V(a, b) = 0 iff a = b,
+/// plus the triangle inequality for a metric
+fn check_metric(
+    table: &[f64],
+    num_labels: usize,
+    requirement: Requirement,
+    potential: usize,
+) -> Result<(), MrfError> {
+    let v = |a: usize, b: usize| table[a * num_labels + b];
+    let fail = |a, b| MrfError::NonMetric { potential, required: requirement.name(), a, b };
+    for a in 0..num_labels {
+        for b in 0..num_labels {
+            let ok = if a == b {
+                v(a, b).abs() <= TOLERANCE
+            } else {
+                v(a, b) > TOLERANCE && (v(a, b) - v(b, a)).abs() <= TOLERANCE
+            };
+            if !ok {
+                return Err(fail(a, b));
+            }
+            if requirement == Requirement::Metric {
+                for c in 0..num_labels {
+                    if v(a, b) > v(a, c) + v(c, b) + TOLERANCE {
+                        return Err(fail(a, b));
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Four, Grid2D};
+    use crate::potentials::{TablePotential, UnaryPotential};
+    use crate::inference::factors::increment;
+
+    /// Truncated linear distance min(|a - b|, 2) over 4 labels
+    fn truncated_linear() -> TablePotential {
+        let scores = (0..16).map(|i| ((i / 4) as f64 - (i % 4) as f64).abs().min(2.0) * 0.4).collect();
+        TablePotential::new(scores, 4, 2).unwrap()
+    }
+
+    fn noisy_grid() -> MRF<usize> {
+        let grid: Grid2D<usize> = Grid2D::new(3, 3, Four);
+        // Observed intensities per node, preferring the nearest label
+        let observed = [0, 0, 1, 0, 3, 1, 2, 2, 3];
+        let unary = UnaryPotential::new(
+            observed.iter().map(|&o: &usize| (0..4).map(|l: usize| (l.abs_diff(o)) as f64 * 0.5).collect()).collect(),
+        ).unwrap();
+        MRF::<usize>::builder().grid(grid).potential(truncated_linear()).potential(unary).build().unwrap()
+    }
+
+    fn brute_force_map(mrf: &mut MRF<usize>, num_labels: usize) -> f64 {
+        let n = mrf.num_nodes();
+        let mut labels = vec![0; n];
+        let mut best = f64::INFINITY;
+        for _ in 0..num_labels.pow(n as u32) {
+            for (i, &l) in labels.iter().enumerate() {
+                mrf.graph_mut().get_node_mut(i).set_state(l);
+            }
+            best = best.min(mrf.energy());
+            increment(&mut labels, num_labels);
+        }
+        for i in 0..n {
+            mrf.graph_mut().get_node_mut(i).set_state(0);
+        }
+        best
+    }
+
+    #[test]
+    fn expansion_reaches_optimum() {
+        let mut mrf = noisy_grid();
+        let optimum = brute_force_map(&mut mrf, 4);
+        let result = AlphaExpansion::new(4, 10).run(&mut mrf).unwrap();
+        assert!((result.energy - optimum).abs() < 1e-9);
+        assert!((mrf.energy() - result.energy).abs() < 1e-9);
+        assert!(result.energies.windows(2).all(|w| w[1] <= w[0]));
+    }
+
+    #[test]
+    fn swap_never_increases_energy() {
+        let mut mrf = noisy_grid();
+        let optimum = brute_force_map(&mut mrf, 4);
+        let start = mrf.energy();
+        let result = AlphaBetaSwap::new(4, 10).run(&mut mrf).unwrap();
+        assert!(result.energy <= start);
+        assert!(result.energy >= optimum - 1e-9);
+        assert!((mrf.energy() - result.energy).abs() < 1e-9);
+    }
+
+    #[test]
+    fn expansion_rejects_non_metric() {
+        // Squared distance violates the triangle inequality
+        let squared = TablePotential::new(
+            (0..9).map(|i| ((i / 3) as f64 - (i % 3) as f64).powi(2)).collect(), 3, 2,
+        ).unwrap();
+        let grid: Grid2D<usize> = Grid2D::new(2, 2, Four);
+        let mut mrf = MRF::<usize>::builder().grid(grid).potential(squared).build().unwrap();
+        assert!(matches!(
+            AlphaExpansion::new(3, 5).run(&mut mrf),
+            Err(MrfError::NonMetric { potential: 0, required: "metric", .. })
+        ));
+        // ...but is a semi-metric, so swap accepts it
+        assert!(AlphaBetaSwap::new(3, 5).run(&mut mrf).is_ok());
+    }
+
+    #[test]
+    fn swap_rejects_nonzero_diagonal() {
+        let grid: Grid2D<usize> = Grid2D::new(2, 2, Four);
+        let pairwise = TablePotential::new(vec![1.0, 2.0, 2.0, 1.0], 2, 2).unwrap();
+        let mut mrf = MRF::<usize>::builder().grid(grid).potential(pairwise).build().unwrap();
+        assert!(matches!(
+            AlphaBetaSwap::new(2, 5).run(&mut mrf),
+            Err(MrfError::NonMetric { required: "semi-metric", a: 0, b: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn moves_leave_clamped_nodes() {
+        for swap in [false, true] {
+            let mut mrf = noisy_grid();
+            mrf.clamp(4, 0).unwrap();
+            let start = mrf.energy();
+            let result = if swap {
+                AlphaBetaSwap::new(4, 10).run(&mut mrf).unwrap()
+            } else {
+                AlphaExpansion::new(4, 10).run(&mut mrf).unwrap()
+            };
+            assert_eq!(result.labels[4], 0);
+            assert_eq!(*mrf.graph().get_node(4).state(), 0);
+            assert!(result.energy <= start);
+        }
+    }
+}
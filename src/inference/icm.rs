@@ -0,0 +1,132 @@
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use crate::MRF;
+use crate::error::MrfError;
+use crate::state::Label;
+use crate::samplers::Proposal;
+
+/// Improvement required before a node changes label
+const TOLERANCE: f64 = 1e-12;
+
+pub struct IcmReport {
+    /// Number of sweeps performed
+    pub sweeps: usize,
+    /// Total energy before the first sweep and after each sweep
+    pub energies: Vec<f64>,
+}
+
+/// Iterated Conditional Modes: greedily moves every free node to its lowest-energy
+/// candidate, in index order, until a sweep changes nothing.
+pub struct Icm {
+    max_sweeps: usize,
+}
+
+impl Icm {
+    pub fn new(max_sweeps: usize) -> Self {
+        Self { max_sweeps }
+    }
+
+    pub fn max_sweeps(&self) -> usize {
+        self.max_sweeps
+    }
+
+    pub fn run<L: Label>(
+        &self,
+        mrf: &mut MRF<L>,
+        proposal: &impl Proposal<L>,
+    ) -> Result<IcmReport, MrfError> {
+        // Proposals may draw candidates at random; a fixed stream keeps ICM deterministic
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut energy = mrf.energy();
+        let mut energies = vec![energy];
+        let mut sweeps = 0;
+
+        while sweeps < self.max_sweeps {
+            sweeps += 1;
+            let mut changed = false;
+            for node in mrf.free_nodes() {
+                let current = mrf.graph().get_node(node).state().clone();
+                let current_energy = mrf.node_energy(node);
+                let mut best: Option<(f64, L)> = None;
+                for candidate in proposal.candidates(&current, &mut rng) {
+                    let e = mrf.node_energy_with(node, &candidate);
+                    if e < current_energy - TOLERANCE && best.as_ref().is_none_or(|(b, _)| e < *b) {
+                        best = Some((e, candidate));
+                    }
+                }
+                if let Some((e, label)) = best {
+                    energy += e - current_energy;
+                    mrf.graph_mut().get_node_mut(node).set_state(label);
+                    changed = true;
+                }
+            }
+            energies.push(energy);
+            if !changed {
+                break;
+            }
+        }
+        Ok(IcmReport { sweeps, energies })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Four, Grid2D};
+    use crate::potentials::{TablePotential, UnaryPotential};
+    use crate::samplers::DiscreteProposal;
+
+    fn noisy_grid() -> MRF<usize> {
+        let mut grid: Grid2D<usize> = Grid2D::new(5, 5, Four);
+        let observed: Vec<usize> = (0..25).map(|i| usize::from((i * 7) % 5 < 2)).collect();
+        for (i, &o) in observed.iter().enumerate() {
+            grid.graph_mut().get_node_mut(i).set_state(o);
+        }
+        let unary = UnaryPotential::new(
+            observed.iter().map(|&o| if o == 0 { vec![0.0, 0.8] } else { vec![0.8, 0.0] }).collect(),
+        ).unwrap();
+        let potts = TablePotential::new(vec![0.0, 0.5, 0.5, 0.0], 2, 2).unwrap();
+        MRF::<usize>::builder().grid(grid).potential(potts).potential(unary).build().unwrap()
+    }
+
+    #[test]
+    fn reaches_local_minimum() {
+        let mut mrf = noisy_grid();
+        let proposal = DiscreteProposal::new(2);
+        let report = Icm::new(50).run(&mut mrf, &proposal).unwrap();
+
+        assert!(report.sweeps < 50);
+        assert_eq!(report.energies.len(), report.sweeps + 1);
+        assert!(report.energies.windows(2).all(|w| w[1] <= w[0]));
+        assert!((report.energies.last().unwrap() - mrf.energy()).abs() < 1e-9);
+
+        // No single-node change improves the result
+        for node in 0..mrf.num_nodes() {
+            let current = mrf.node_energy(node);
+            for label in 0..2 {
+                assert!(mrf.node_energy_with(node, &label) >= current - 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn is_deterministic() {
+        let proposal = DiscreteProposal::new(2);
+        let mut a = noisy_grid();
+        let mut b = noisy_grid();
+        Icm::new(50).run(&mut a, &proposal).unwrap();
+        Icm::new(50).run(&mut b, &proposal).unwrap();
+        for i in 0..a.num_nodes() {
+            assert_eq!(a.graph().get_node(i).state(), b.graph().get_node(i).state());
+        }
+    }
+
+    #[test]
+    fn leaves_clamped_nodes() {
+        let mut mrf = noisy_grid();
+        let label = 1 - *mrf.graph().get_node(12).state();
+        mrf.clamp(12, label).unwrap();
+        Icm::new(50).run(&mut mrf, &DiscreteProposal::new(2)).unwrap();
+        assert_eq!(*mrf.graph().get_node(12).state(), label);
+    }
+}
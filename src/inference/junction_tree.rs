@@ -0,0 +1,432 @@
+use std::collections::BTreeSet;
+use crate::MRF;
+use crate::error::MrfError;
+use crate::graph::{Clique, Graph};
+use crate::inference::factors::{FactorTables, increment, log_sum_exp};
+
+/// Greedy heuristic choosing the next node to eliminate during triangulation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Elimination {
+    /// Fewest fill-in edges added
+    MinFill,
+    /// Fewest remaining neighbours
+    MinDegree,
+}
+
+pub struct JunctionTreeResult {
+    /// ln Z for p(x) = exp(-energy(x)) / Z, summed over labellings that agree with clamped nodes
+    pub log_partition: f64,
+    /// Per-node marginal over labels
+    pub marginals: Vec<Vec<f64>>,
+    /// Cliques of the triangulated graph, i.e. the junction tree's nodes
+    pub cliques: Vec<Clique>,
+    /// Largest clique size minus one
+    pub treewidth: usize,
+}
+
+/// Exact inference by Shafer-Shenoy message passing on a junction tree.
+/// The interaction graph (one edge per pair of nodes sharing a clique instance)
+/// is triangulated by greedy elimination, its maximal cliques are joined by a
+/// maximum-weight spanning tree over separator sizes, and every clique instance
+/// is assigned to one junction tree clique covering it. Clamped nodes are
+/// conditioned on.
+pub struct JunctionTree {
+    num_labels: usize,
+    elimination: Elimination,
+    max_table_size: u64,
+}
+
+impl JunctionTree {
+    /// Refuses triangulations whose clique tables exceed `max_table_size` entries
+    pub fn new(num_labels: usize, elimination: Elimination, max_table_size: u64) -> Self {
+        Self { num_labels, elimination, max_table_size }
+    }
+
+    pub fn num_labels(&self) -> usize {
+        self.num_labels
+    }
+
+    pub fn elimination(&self) -> Elimination {
+        self.elimination
+    }
+
+    pub fn max_table_size(&self) -> u64 {
+        self.max_table_size
+    }
+
+    pub fn run(&self, mrf: &MRF<usize>) -> Result<JunctionTreeResult, MrfError> {
+        let mut tables = FactorTables::new(mrf, self.num_labels)?;
+        tables.condition(mrf)?;
+        let n = mrf.num_nodes();
+        let l = self.num_labels;
+
+        let cliques = self.triangulated_cliques(&tables);
+        let treewidth = cliques.iter().map(|c| c.len()).max().unwrap_or(1) - 1;
+        for c in &cliques {
+            let size = u32::try_from(c.len()).ok().and_then(|k| (l as u64).checked_pow(k));
+            if size.is_none_or(|s| s > self.max_table_size) {
+                return Err(MrfError::TooManyStates { num_labels: l, num_nodes: c.len(), limit: self.max_table_size });
+            }
+        }
+
+        // Log-potential of each clique from the factors assigned to it
+        let mut potentials: Vec<Vec<f64>> = cliques.iter().map(|c| vec![0.0; l.pow(c.len() as u32)]).collect();
+        for f in &tables.factors {
+            let home = cliques.iter()
+                .position(|c| f.members.iter().all(|&m| c.contains(m)))
+                .expect("every factor scope is covered by a triangulated clique");
+            let positions = positions_in(cliques[home].members(), &f.members);
+            let mut labels = vec![0; cliques[home].len()];
+            for p in potentials[home].iter_mut() {
+                *p -= f.energies[sub_index(&labels, &positions, l)];
+                increment(&mut labels, l);
+            }
+        }
+
+        let tree = spanning_tree(&cliques);
+        let mut inbox: Vec<Vec<(usize, Vec<f64>)>> = vec![Vec::new(); cliques.len()];
+        let mut visited = vec![false; cliques.len()];
+        let mut log_partition = 0.0;
+
+        for root in 0..cliques.len() {
+            if visited[root] {
+                continue;
+            }
+            // Depth-first order from the root, with parents
+            let mut order = Vec::new();
+            let mut stack = vec![(root, usize::MAX)];
+            visited[root] = true;
+            while let Some((c, parent)) = stack.pop() {
+                order.push((c, parent));
+                for &next in &tree[c] {
+                    if !visited[next] {
+                        visited[next] = true;
+                        stack.push((next, c));
+                    }
+                }
+            }
+
+            // Collect towards the root, then distribute away from it
+            for &(c, parent) in order.iter().rev() {
+                if parent != usize::MAX {
+                    let msg = message(&cliques, &potentials, &inbox, c, parent, l);
+                    inbox[parent].push((c, msg));
+                }
+            }
+            for &(c, parent) in &order {
+                for &child in &tree[c] {
+                    if child != parent {
+                        let msg = message(&cliques, &potentials, &inbox, c, child, l);
+                        inbox[child].push((c, msg));
+                    }
+                }
+            }
+            log_partition += log_sum_exp(&belief(&cliques, &potentials, &inbox, root, l));
+        }
+
+        let mut marginals = vec![vec![1.0 / l as f64; l]; n];
+        let mut done = vec![false; n];
+        for (ci, c) in cliques.iter().enumerate() {
+            let b = belief(&cliques, &potentials, &inbox, ci, l);
+            for (pos, &node) in c.members().iter().enumerate() {
+                if done[node] {
+                    continue;
+                }
+                let mut log_m = vec![f64::NEG_INFINITY; l];
+                let mut labels = vec![0; c.len()];
+                for &v in &b {
+                    let m = &mut log_m[labels[pos]];
+                    *m = log_sum_exp(&[*m, v]);
+                    increment(&mut labels, l);
+                }
+                let z = log_sum_exp(&log_m);
+                marginals[node] = log_m.iter().map(|v| (v - z).exp()).collect();
+                done[node] = true;
+            }
+        }
+
+        Ok(JunctionTreeResult { log_partition, marginals, cliques, treewidth })
+    }
+
+    /// Maximal cliques of the interaction graph after greedy elimination,
+    /// plus singletons for nodes that end up in no edge
+    fn triangulated_cliques(&self, tables: &FactorTables) -> Vec<Clique> {
+        let n = tables.num_nodes();
+        let mut adjacency = vec![BTreeSet::new(); n];
+        for f in &tables.factors {
+            for &a in &f.members {
+                for &b in &f.members {
+                    if a != b {
+                        adjacency[a].insert(b);
+                    }
+                }
+            }
+        }
+
+        let mut graph: Graph<()> = Graph::new(n);
+        for (a, neighbors) in adjacency.iter().enumerate() {
+            for &b in neighbors.iter().filter(|&&b| a < b) {
+                graph.add_edge(a, b);
+            }
+        }
+
+        let mut eliminated = vec![false; n];
+        for _ in 0..n {
+            let cost = |v: usize| match self.elimination {
+                Elimination::MinDegree => adjacency[v].len(),
+                Elimination::MinFill => {
+                    let nb: Vec<usize> = adjacency[v].iter().copied().collect();
+                    let mut missing = 0;
+                    for (i, &a) in nb.iter().enumerate() {
+                        missing += nb[i + 1..].iter().filter(|&&b| !adjacency[a].contains(&b)).count();
+                    }
+                    missing
+                }
+            };
+            let v = (0..n).filter(|&v| !eliminated[v]).min_by_key(|&v| cost(v)).unwrap();
+            let nb: Vec<usize> = adjacency[v].iter().copied().collect();
+            for (i, &a) in nb.iter().enumerate() {
+                for &b in &nb[i + 1..] {
+                    if adjacency[a].insert(b) {
+                        adjacency[b].insert(a);
+                        graph.add_edge(a, b);
+                    }
+                }
+            }
+            for &a in &nb {
+                adjacency[a].remove(&v);
+            }
+            adjacency[v].clear();
+            eliminated[v] = true;
+        }
+
+        graph.detect_cliques();
+        let mut cliques = graph.maximal_cliques().to_vec();
+        for v in 0..n {
+            if graph.get_node(v).neighbors().is_empty() {
+                cliques.push(Clique::new(vec![v]));
+            }
+        }
+        cliques
+    }
+}
+
+/// Maximum-weight spanning forest over separator sizes (Kruskal), as adjacency lists
+fn spanning_tree(cliques: &[Clique]) -> Vec<Vec<usize>> {
+    let mut candidates = Vec::new();
+    for i in 0..cliques.len() {
+        for j in i + 1..cliques.len() {
+            let w = cliques[i].intersect(&cliques[j]).len();
+            if w > 0 {
+                candidates.push((w, i, j));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.0.cmp(&a.0).then((a.1, a.2).cmp(&(b.1, b.2))));
+
+    let mut parent: Vec<usize> = (0..cliques.len()).collect();
+    fn find(parent: &mut [usize], mut x: usize) -> usize {
+        while parent[x] != x {
+            parent[x] = parent[parent[x]];
+            x = parent[x];
+        }
+        x
+    }
+    let mut tree = vec![Vec::new(); cliques.len()];
+    for (_, i, j) in candidates {
+        let (ri, rj) = (find(&mut parent, i), find(&mut parent, j));
+        if ri != rj {
+            parent[ri] = rj;
+            tree[i].push(j);
+            tree[j].push(i);
+        }
+    }
+    tree
+}
+
+/// Positions of `subset` members within `members`
+fn positions_in(members: &[usize], subset: &[usize]) -> Vec<usize> {
+    subset.iter().map(|s| members.iter().position(|m| m == s).unwrap()).collect()
+}
+
+/// Table index of the sub-labelling picked out by `positions`
+fn sub_index(labels: &[usize], positions: &[usize], num_labels: usize) -> usize {
+    positions.iter().fold(0, |idx, &p| idx * num_labels + labels[p])
+}
+
+/// Clique log-potential plus every message received so far
+fn belief(
+    cliques: &[Clique],
+    potentials: &[Vec<f64>],
+    inbox: &[Vec<(usize, Vec<f64>)>],
+    clique: usize,
+    num_labels: usize,
+) -> Vec<f64> {
+    belief_excluding(cliques, potentials, inbox, clique, usize::MAX, num_labels)
+}
+
+fn belief_excluding(
+    cliques: &[Clique],
+    potentials: &[Vec<f64>],
+    inbox: &[Vec<(usize, Vec<f64>)>],
+    clique: usize,
+    excluded: usize,
+    num_labels: usize,
+) -> Vec<f64> {
+    let members = cliques[clique].members();
+    let mut b = potentials[clique].clone();
+    for (from, msg) in &inbox[clique] {
+        if *from == excluded {
+            continue;
+        }
+        let separator = cliques[clique].intersect(&cliques[*from]);
+        let positions = positions_in(members, separator.members());
+        let mut labels = vec![0; members.len()];
+        for v in b.iter_mut() {
+            *v += msg[sub_index(&labels, &positions, num_labels)];
+            increment(&mut labels, num_labels);
+        }
+    }
+    b
+}
+
+/// Shafer-Shenoy message from one clique to a neighbour, over their separator
+fn message(
+    cliques: &[Clique],
+    potentials: &[Vec<f64>],
+    inbox: &[Vec<(usize, Vec<f64>)>],
+    from: usize,
+    to: usize,
+    num_labels: usize,
+) -> Vec<f64> {
+    let b = belief_excluding(cliques, potentials, inbox, from, to, num_labels);
+    let separator = cliques[from].intersect(&cliques[to]);
+    let positions = positions_in(cliques[from].members(), separator.members());
+    let mut buckets = vec![Vec::new(); num_labels.pow(separator.len() as u32)];
+    let mut labels = vec![0; cliques[from].len()];
+    for v in b {
+        buckets[sub_index(&labels, &positions, num_labels)].push(v);
+        increment(&mut labels, num_labels);
+    }
+    buckets.iter().map(|bucket| log_sum_exp(bucket)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Four, Grid2D};
+    use crate::inference::ExactInference;
+    use crate::potentials::{TablePotential, UnaryPotential};
+
+    fn assert_matches_exact(mrf: &MRF<usize>, num_labels: usize) -> JunctionTreeResult {
+        let exact = ExactInference::new(num_labels, 1 << 20).run(mrf).unwrap();
+        for elimination in [Elimination::MinFill, Elimination::MinDegree] {
+            let jt = JunctionTree::new(num_labels, elimination, 1 << 16).run(mrf).unwrap();
+            assert!((jt.log_partition - exact.log_partition).abs() < 1e-9);
+            for (a, b) in jt.marginals.iter().zip(&exact.marginals) {
+                for (x, y) in a.iter().zip(b) {
+                    assert!((x - y).abs() < 1e-9, "{x} vs {y}");
+                }
+            }
+        }
+        JunctionTree::new(num_labels, Elimination::MinFill, 1 << 16).run(mrf).unwrap()
+    }
+
+    fn field(n: usize, num_labels: usize) -> UnaryPotential {
+        UnaryPotential::new(
+            (0..n).map(|i| (0..num_labels).map(|l| ((i * 3 + l * 5) % 7) as f64 * 0.2).collect()).collect(),
+        ).unwrap()
+    }
+
+    #[test]
+    fn exact_on_chain() {
+        let mut g = Graph::new(8);
+        for i in 0..7 {
+            g.add_edge(i, i + 1);
+        }
+        g.detect_cliques();
+        let pairwise = TablePotential::new(vec![0.0, 0.4, 1.1, 0.4, 0.0, 0.6, 1.1, 0.6, 0.0], 3, 2).unwrap();
+        let mrf = MRF::<usize>::builder().graph(g).potential(pairwise).potential(field(8, 3)).build().unwrap();
+        let jt = assert_matches_exact(&mrf, 3);
+        assert_eq!(jt.treewidth, 1);
+        assert_eq!(jt.cliques.len(), 7);
+    }
+
+    #[test]
+    fn exact_on_grid_strip() {
+        let grid: Grid2D<usize> = Grid2D::new(4, 3, Four);
+        let potts = TablePotential::new(vec![0.0, 0.9, 0.9, 0.0], 2, 2).unwrap();
+        let mrf = MRF::<usize>::builder().grid(grid).potential(potts).potential(field(12, 2)).build().unwrap();
+        let jt = assert_matches_exact(&mrf, 2);
+        assert!(jt.treewidth <= 3);
+    }
+
+    #[test]
+    fn exact_with_triangles_and_isolated_node() {
+        let mut g = Graph::new(5);
+        g.add_edge(0, 1);
+        g.add_edge(1, 2);
+        g.add_edge(0, 2);
+        g.add_edge(2, 3);
+        g.detect_cliques();
+        let ternary = TablePotential::new((0..8).map(|i| (i as f64 * 0.9).cos()).collect(), 2, 3).unwrap();
+        let potts = TablePotential::new(vec![0.0, 0.5, 0.5, 0.0], 2, 2).unwrap();
+        let mrf = MRF::<usize>::builder()
+            .graph(g)
+            .potential(ternary)
+            .potential(potts)
+            .potential(field(5, 2))
+            .build()
+            .unwrap();
+        let jt = assert_matches_exact(&mrf, 2);
+        assert_eq!(jt.treewidth, 2);
+    }
+
+    #[test]
+    fn exact_with_patch_cliques() {
+        // Order-4 factors over every 2x2 block, which share no graph clique
+        let grid: Grid2D<usize> = Grid2D::new(3, 3, Four);
+        let patches = grid.patches(2);
+        let block = TablePotential::new((0..16).map(|i| (i as f64 * 0.7).sin()).collect(), 2, 4).unwrap();
+        let mrf = MRF::<usize>::builder()
+            .grid(grid)
+            .potential_on(block, patches)
+            .potential(field(9, 2))
+            .build()
+            .unwrap();
+        let jt = assert_matches_exact(&mrf, 2);
+        assert!(jt.treewidth >= 3);
+    }
+
+    #[test]
+    fn refuses_wide_cliques() {
+        let grid: Grid2D<usize> = Grid2D::new(4, 4, Four);
+        let potts = TablePotential::new(vec![0.0, 0.9, 0.9, 0.0], 2, 2).unwrap();
+        let mrf = MRF::<usize>::builder().grid(grid).potential(potts).build().unwrap();
+        let result = JunctionTree::new(2, Elimination::MinFill, 4).run(&mrf);
+        assert!(matches!(result, Err(MrfError::TooManyStates { limit: 4, .. })));
+    }
+
+    #[test]
+    fn exact_with_clamped_nodes() {
+        let grid: Grid2D<usize> = Grid2D::new(4, 3, Four);
+        let potts = TablePotential::new(vec![0.0, 0.9, 0.9, 0.0], 2, 2).unwrap();
+        let mut mrf = MRF::<usize>::builder().grid(grid).potential(potts).potential(field(12, 2)).build().unwrap();
+        mrf.clamp(0, 1).unwrap();
+        mrf.clamp(6, 0).unwrap();
+        let jt = assert_matches_exact(&mrf, 2);
+        assert_eq!(jt.marginals[0], vec![0.0, 1.0]);
+    }
+
+    #[test]
+    fn exact_with_isolated_clamped_node() {
+        let mut g = Graph::new(3);
+        g.add_edge(0, 1);
+        g.detect_cliques();
+        let potts = TablePotential::new(vec![0.0, 0.7, 0.7, 0.0], 2, 2).unwrap();
+        let mut mrf = MRF::<usize>::builder().graph(g).potential(potts).build().unwrap();
+        mrf.clamp(2, 1).unwrap();
+        let jt = assert_matches_exact(&mrf, 2);
+        assert_eq!(jt.marginals[2], vec![0.0, 1.0]);
+    }
+}
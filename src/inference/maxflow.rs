@@ -0,0 +1,159 @@
+use std::collections::VecDeque;
+
+const EPSILON: f64 = 1e-12;
+
+struct Edge {
+    to: usize,
+    capacity: f64,
+}
+
+/// Dinic max-flow / min-cut on a directed graph with real capacities.
+/// Edges are stored in pairs so that `e ^ 1` is the reverse of `e`.
+pub(crate) struct MaxFlow {
+    edges: Vec<Edge>,
+    adjacency: Vec<Vec<usize>>,
+    level: Vec<usize>,
+    next: Vec<usize>,
+}
+
+impl MaxFlow {
+    pub fn new(num_vertices: usize) -> Self {
+        Self {
+            edges: Vec::new(),
+            adjacency: vec![Vec::new(); num_vertices],
+            level: vec![0; num_vertices],
+            next: vec![0; num_vertices],
+        }
+    }
+
+    pub fn add_edge(&mut self, from: usize, to: usize, capacity: f64) {
+        debug_assert!(capacity >= 0.0);
+        self.adjacency[from].push(self.edges.len());
+        self.edges.push(Edge { to, capacity });
+        self.adjacency[to].push(self.edges.len());
+        self.edges.push(Edge { to: from, capacity: 0.0 });
+    }
+
+    /// Pushes the maximum flow from `source` to `sink` and returns its value
+    pub fn solve(&mut self, source: usize, sink: usize) -> f64 {
+        let mut flow = 0.0;
+        while self.build_levels(source, sink) {
+            self.next.iter_mut().for_each(|n| *n = 0);
+            loop {
+                let pushed = self.augment(source, sink);
+                if pushed <= EPSILON {
+                    break;
+                }
+                flow += pushed;
+            }
+        }
+        flow
+    }
+
+    /// After `solve`, whether a vertex is on the source side of the minimum cut
+    pub fn on_source_side(&self, vertex: usize) -> bool {
+        self.level[vertex] != usize::MAX
+    }
+
+    /// Breadth-first levels over residual edges; false once the sink is unreachable
+    fn build_levels(&mut self, source: usize, sink: usize) -> bool {
+        self.level.iter_mut().for_each(|l| *l = usize::MAX);
+        self.level[source] = 0;
+        let mut queue = VecDeque::from([source]);
+        while let Some(v) = queue.pop_front() {
+            for &e in &self.adjacency[v] {
+                let edge = &self.edges[e];
+                if edge.capacity > EPSILON && self.level[edge.to] == usize::MAX {
+                    self.level[edge.to] = self.level[v] + 1;
+                    queue.push_back(edge.to);
+                }
+            }
+        }
+        self.level[sink] != usize::MAX
+    }
+
+    /// Finds one augmenting path in the level graph and pushes its bottleneck.
+    /// Depth-first with an explicit path, so long paths cannot overflow the stack;
+    /// `next` skips edges already found to be saturated or dead ends.
+    fn augment(&mut self, source: usize, sink: usize) -> f64 {
+        let mut path: Vec<usize> = Vec::new();
+        let mut v = source;
+        loop {
+            if v == sink {
+                let pushed = path.iter().map(|&e| self.edges[e].capacity).fold(f64::INFINITY, f64::min);
+                for &e in &path {
+                    self.edges[e].capacity -= pushed;
+                    self.edges[e ^ 1].capacity += pushed;
+                }
+                return pushed;
+            }
+
+            let mut advanced = false;
+            while self.next[v] < self.adjacency[v].len() {
+                let e = self.adjacency[v][self.next[v]];
+                let to = self.edges[e].to;
+                if self.edges[e].capacity > EPSILON && self.level[to] == self.level[v] + 1 {
+                    path.push(e);
+                    v = to;
+                    advanced = true;
+                    break;
+                }
+                self.next[v] += 1;
+            }
+
+            if !advanced {
+                // Dead end: step back and skip the edge that led here
+                match path.pop() {
+                    Some(e) => {
+                        v = self.edges[e ^ 1].to;
+                        self.next[v] += 1;
+                    }
+                    None => return 0.0,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn textbook_network() {
+        // CLRS example: max flow 23
+        let mut mf = MaxFlow::new(6);
+        for &(a, b, c) in &[
+            (0, 1, 16.0), (0, 2, 13.0), (1, 3, 12.0), (2, 1, 4.0), (2, 4, 14.0),
+            (3, 2, 9.0), (3, 5, 20.0), (4, 3, 7.0), (4, 5, 4.0),
+        ] {
+            mf.add_edge(a, b, c);
+        }
+        assert!((mf.solve(0, 5) - 23.0).abs() < 1e-9);
+        assert!(mf.on_source_side(0));
+        assert!(!mf.on_source_side(5));
+    }
+
+    #[test]
+    fn min_cut_separates_bottleneck() {
+        let mut mf = MaxFlow::new(4);
+        mf.add_edge(0, 1, 5.0);
+        mf.add_edge(1, 2, 0.5);
+        mf.add_edge(2, 3, 5.0);
+        assert!((mf.solve(0, 3) - 0.5).abs() < 1e-9);
+        assert!(mf.on_source_side(1));
+        assert!(!mf.on_source_side(2));
+    }
+
+    #[test]
+    fn long_chain_does_not_overflow() {
+        let n = 100_000;
+        let mut mf = MaxFlow::new(n);
+        for v in 0..n - 1 {
+            mf.add_edge(v, v + 1, if v == n / 2 { 0.25 } else { 1.0 + (v % 7) as f64 });
+        }
+        assert!((mf.solve(0, n - 1) - 0.25).abs() < 1e-9);
+        assert!(mf.on_source_side(n / 2));
+        assert!(!mf.on_source_side(n / 2 + 1));
+    }
+}
@@ -0,0 +1,244 @@
+use std::thread;
+use crate::MRF;
+use crate::error::MrfError;
+use crate::inference::factors::{FactorTables, clamped_labels, increment, normalize_log};
+use crate::samplers::Annealer;
+
+pub struct MeanFieldOptions {
+    pub max_iterations: usize,
+    /// Stop once no marginal probability changes by more than this
+    pub tolerance: f64,
+    /// Weight kept from the previous distribution, in [0, 1)
+    pub damping: f64,
+}
+
+impl Default for MeanFieldOptions {
+    fn default() -> Self {
+        Self {
+            max_iterations: 200,
+            tolerance: 1e-6,
+            damping: 0.5,
+        }
+    }
+}
+
+pub struct MeanFieldResult {
+    /// Per-node distribution q_i over labels
+    pub marginals: Vec<Vec<f64>>,
+    /// E_q[energy] - T * H(q) at the final temperature; an upper bound on -T ln Z
+    pub free_energy: f64,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+/// Naive mean-field: approximates p(x) ∝ exp(-energy(x) / T) by a product of
+/// per-node distributions. Iteration i runs at `annealer.temperature(i)`, as a
+/// Gibbs sweep would. All free nodes update synchronously from the previous
+/// iteration's distributions, in parallel and deterministically; clamped nodes
+/// keep all their mass on their label.
+pub struct MeanField<A: Annealer> {
+    num_labels: usize,
+    annealer: A,
+    opts: MeanFieldOptions,
+}
+
+impl<A: Annealer> MeanField<A> {
+    pub fn new(num_labels: usize, annealer: A, opts: MeanFieldOptions) -> Self {
+        Self { num_labels, annealer, opts }
+    }
+
+    pub fn num_labels(&self) -> usize {
+        self.num_labels
+    }
+
+    pub fn annealer(&self) -> &A {
+        &self.annealer
+    }
+
+    pub fn options(&self) -> &MeanFieldOptions {
+        &self.opts
+    }
+
+    pub fn run(&self, mrf: &MRF<usize>) -> Result<MeanFieldResult, MrfError> {
+        if !(0.0..1.0).contains(&self.opts.damping) {
+            return Err(MrfError::InvalidParameter { name: "damping", value: self.opts.damping });
+        }
+        let tables = FactorTables::new(mrf, self.num_labels)?;
+        let n = tables.num_nodes();
+        let l = self.num_labels;
+        let threads = thread::available_parallelism().map_or(1, |t| t.get());
+        let chunk = n.div_ceil(threads).max(1);
+
+        let evidence = clamped_labels(mrf, l)?;
+        // Clamped nodes keep a point mass on their label
+        let point = |label: usize| (0..l).map(|k| if k == label { 1.0 } else { 0.0 }).collect::<Vec<f64>>();
+        let mut q: Vec<Vec<f64>> = evidence.iter()
+            .map(|e| e.map_or_else(|| vec![1.0 / l as f64; l], point))
+            .collect();
+        let mut iterations = 0;
+        let mut converged = false;
+        let mut temperature = self.annealer.temperature(0);
+
+        while iterations < self.opts.max_iterations {
+            temperature = self.annealer.temperature(iterations);
+            if temperature <= 0.0 {
+                return Err(MrfError::InvalidParameter { name: "temperature", value: temperature });
+            }
+            iterations += 1;
+
+            let mut next = vec![Vec::new(); n];
+            thread::scope(|s| {
+                for (c, out) in next.chunks_mut(chunk).enumerate() {
+                    let (tables, q, evidence, point) = (&tables, &q, &evidence, &point);
+                    s.spawn(move || {
+                        for (k, dst) in out.iter_mut().enumerate() {
+                            let node = c * chunk + k;
+                            if let Some(label) = evidence[node] {
+                                *dst = point(label);
+                                continue;
+                            }
+                            let log_q: Vec<f64> = expected_node_energy(tables, q, node).iter()
+                                .map(|e| -e / temperature)
+                                .collect();
+                            *dst = normalize_log(&log_q);
+                        }
+                    });
+                }
+            });
+
+            let mut delta: f64 = 0.0;
+            for (old, new) in q.iter_mut().zip(next) {
+                for (o, v) in old.iter_mut().zip(new) {
+                    let damped = self.opts.damping * *o + (1.0 - self.opts.damping) * v;
+                    delta = delta.max((damped - *o).abs());
+                    *o = damped;
+                }
+            }
+            if delta < self.opts.tolerance {
+                converged = true;
+                break;
+            }
+        }
+
+        let free_energy = expected_energy(&tables, &q) - temperature * entropy(&q);
+        Ok(MeanFieldResult { marginals: q, free_energy, iterations, converged })
+    }
+}
+
+/// Expected energy of the factors touching a node, per label of that node
+fn expected_node_energy(tables: &FactorTables, q: &[Vec<f64>], node: usize) -> Vec<f64> {
+    let mut energy = vec![0.0; tables.num_labels];
+    for &(fi, pos) in &tables.node_factors[node] {
+        let f = &tables.factors[fi];
+        let mut labels = vec![0; f.members.len()];
+        for &e in &f.energies {
+            let w: f64 = f.members.iter().enumerate()
+                .filter(|&(p, _)| p != pos)
+                .map(|(p, &m)| q[m][labels[p]])
+                .product();
+            energy[labels[pos]] += w * e;
+            increment(&mut labels, tables.num_labels);
+        }
+    }
+    energy
+}
+
+/// E_q[energy] under the factorized distribution
+fn expected_energy(tables: &FactorTables, q: &[Vec<f64>]) -> f64 {
+    let mut total = 0.0;
+    for f in &tables.factors {
+        let mut labels = vec![0; f.members.len()];
+        for &e in &f.energies {
+            let w: f64 = f.members.iter().zip(&labels).map(|(&m, &l)| q[m][l]).product();
+            total += w * e;
+            increment(&mut labels, tables.num_labels);
+        }
+    }
+    total
+}
+
+fn entropy(q: &[Vec<f64>]) -> f64 {
+    -q.iter().flatten().filter(|&&p| p > 0.0).map(|p| p * p.ln()).sum::<f64>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Four, Grid2D};
+    use crate::inference::ExactInference;
+    use crate::potentials::{TablePotential, UnaryPotential};
+    use crate::samplers::ConstantAnnealer;
+
+    fn field(n: usize) -> UnaryPotential {
+        UnaryPotential::new((0..n).map(|i| vec![0.0, 0.3 * (i % 4) as f64 - 0.4]).collect()).unwrap()
+    }
+
+    #[test]
+    fn exact_without_interactions() {
+        let grid: Grid2D<usize> = Grid2D::new(3, 3, Four);
+        let mrf = MRF::<usize>::builder().grid(grid).potential(field(9)).build().unwrap();
+        let exact = ExactInference::new(2, 1 << 10).run(&mrf).unwrap();
+        let mf = MeanField::new(2, ConstantAnnealer::new(1.0), MeanFieldOptions::default()).run(&mrf).unwrap();
+        assert!(mf.converged);
+        assert!((mf.free_energy + exact.log_partition).abs() < 1e-6);
+        for (a, b) in mf.marginals.iter().zip(&exact.marginals) {
+            assert!((a[1] - b[1]).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn free_energy_bounds_log_partition() {
+        let grid: Grid2D<usize> = Grid2D::new(3, 3, Four);
+        let potts = TablePotential::new(vec![0.0, 0.6, 0.6, 0.0], 2, 2).unwrap();
+        let mrf = MRF::<usize>::builder().grid(grid).potential(potts).potential(field(9)).build().unwrap();
+        let exact = ExactInference::new(2, 1 << 10).run(&mrf).unwrap();
+        let mf = MeanField::new(2, ConstantAnnealer::new(1.0), MeanFieldOptions::default()).run(&mrf).unwrap();
+        assert!(mf.converged);
+        assert!(mf.free_energy >= -exact.log_partition - 1e-9);
+        for (a, b) in mf.marginals.iter().zip(&exact.marginals) {
+            assert!((a.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+            assert!((a[1] - b[1]).abs() < 0.15);
+        }
+    }
+
+    #[test]
+    fn low_temperature_sharpens_marginals() {
+        let grid: Grid2D<usize> = Grid2D::new(3, 3, Four);
+        let mrf = MRF::<usize>::builder().grid(grid).potential(field(9)).build().unwrap();
+        let opts = || MeanFieldOptions { damping: 0.0, ..Default::default() };
+        let hot = MeanField::new(2, ConstantAnnealer::new(1.0), opts()).run(&mrf).unwrap();
+        let cold = MeanField::new(2, ConstantAnnealer::new(0.05), opts()).run(&mrf).unwrap();
+        // Node 3 strongly prefers label 0 (field +0.5 on label 1)
+        assert!(cold.marginals[3][0] > hot.marginals[3][0]);
+        assert!(cold.marginals[3][0] > 0.99);
+    }
+
+    #[test]
+    fn clamped_nodes_keep_point_mass() {
+        let grid: Grid2D<usize> = Grid2D::new(3, 3, Four);
+        let mut mrf = MRF::<usize>::builder().grid(grid).potential(field(9)).build().unwrap();
+        mrf.clamp(3, 1).unwrap();
+        let exact = ExactInference::new(2, 1 << 10).run(&mrf).unwrap();
+        let mf = MeanField::new(2, ConstantAnnealer::new(1.0), MeanFieldOptions::default()).run(&mrf).unwrap();
+        assert_eq!(mf.marginals[3], vec![0.0, 1.0]);
+        assert!((mf.free_energy + exact.log_partition).abs() < 1e-6);
+    }
+
+    #[test]
+    fn isolated_clamped_node_keeps_point_mass() {
+        let mut g = crate::graph::Graph::new(3);
+        g.add_edge(0, 1);
+        g.detect_cliques();
+        // 0.3 a + 0.5 b: the pair factorizes, so mean field is exact
+        let separable = TablePotential::new(vec![0.0, 0.5, 0.3, 0.8], 2, 2).unwrap();
+        let mut mrf = MRF::<usize>::builder().graph(g).potential(separable).build().unwrap();
+        mrf.clamp(2, 1).unwrap();
+        let exact = ExactInference::new(2, 1 << 10).run(&mrf).unwrap();
+        let mf = MeanField::new(2, ConstantAnnealer::new(1.0), MeanFieldOptions::default()).run(&mrf).unwrap();
+        assert_eq!(mf.marginals[2], vec![0.0, 1.0]);
+        for (a, b) in mf.marginals.iter().zip(&exact.marginals) {
+            assert!((a[1] - b[1]).abs() < 1e-6);
+        }
+        assert!((mf.free_energy + exact.log_partition).abs() < 1e-6);
+    }
+}
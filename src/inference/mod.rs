@@ -0,0 +1,14 @@
+pub(crate) mod factors;
+mod bp;
+mod maxflow;
+mod graphcut;
+mod icm;
+mod exact;
+mod junction_tree;
+mod mean_field;
+pub use self::bp::{BeliefPropagation, BpOptions, BpResult, MapResult, MaxProductBp, Schedule};
+pub use self::graphcut::{AlphaBetaSwap, AlphaExpansion, CutResult};
+pub use self::icm::{Icm, IcmReport};
+pub use self::exact::{ExactInference, ExactResult};
+pub use self::junction_tree::{Elimination, JunctionTree, JunctionTreeResult};
+pub use self::mean_field::{MeanField, MeanFieldOptions, MeanFieldResult};
@@ -1,13 +1,17 @@
 mod graph;
 mod model;
+mod factor_graph;
 mod state;
 mod potentials;
 mod error;
 mod samplers;
+mod inference;
 
 pub use graph::*;
 pub use model::*;
+pub use factor_graph::*;
 pub use state::*;
 pub use potentials::*;
 pub use error::*;
-pub use samplers::*;
\ No newline at end of file
+pub use samplers::*;
+pub use inference::*;
\ No newline at end of file
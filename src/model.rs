@@ -1,6 +1,8 @@
+use std::collections::HashMap;
 use std::marker::PhantomData;
+use smallvec::SmallVec;
 use crate::graph::Neighborhood;
-use crate::graph::Graph;
+use crate::graph::{Clique, Graph, Layout, LayoutGraph};
 use crate::state::Label;
 use crate::potentials::CliquePotential;
 use crate::error::MrfError;
@@ -18,7 +20,22 @@ impl MrfBuilderInit {
     pub fn graph<L: Label>(self, g: Graph<L>) -> MrfBuilder<L, Provided, Missing> {
         MrfBuilder {
             graph: Some(g),
+            layout: None,
             potentials: Vec::new(),
+            bindings: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Uses a lattice's graph (`Grid2D`, `Grid3D` or `Lattice`) and keeps its layout
+    /// for mapping nodes back to pixels or voxels
+    pub fn grid<L: Label>(self, g: impl LayoutGraph<L>) -> MrfBuilder<L, Provided, Missing> {
+        let (layout, graph) = g.into_layout_graph();
+        MrfBuilder {
+            graph: Some(graph),
+            layout: Some(layout),
+            potentials: Vec::new(),
+            bindings: Vec::new(),
             _marker: PhantomData,
         }
     }
@@ -26,7 +43,10 @@ impl MrfBuilderInit {
 
 pub struct MrfBuilder<L, HasGraph, HasPotentials> {
     graph: Option<Graph<L>>,
+    layout: Option<Layout>,
     potentials: Vec<Box<dyn CliquePotential<L>>>,
+    /// Explicit clique instances per potential; None means every clique of its order
+    bindings: Vec<Option<Vec<Clique>>>,
     _marker: PhantomData<(L, HasGraph, HasPotentials)>,
 }
 
@@ -34,7 +54,9 @@ impl Default for MrfBuilder<(), Missing, Missing> {
     fn default() -> Self {
         MrfBuilder {
             graph: None,
+            layout: None,
             potentials: Vec::new(),
+            bindings: Vec::new(),
             _marker: PhantomData,
         }
     }
@@ -44,7 +66,22 @@ impl<L: Label, HasPotentials> MrfBuilder<L, Missing, HasPotentials> {
     pub fn graph(self, g: Graph<L>) -> MrfBuilder<L, Provided, HasPotentials> {
         MrfBuilder {
             graph: Some(g),
+            layout: None,
+            potentials: self.potentials,
+            bindings: self.bindings,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<L: Label, HasPotentials> MrfBuilder<L, Missing, HasPotentials> {
+    pub fn grid(self, g: impl LayoutGraph<L>) -> MrfBuilder<L, Provided, HasPotentials> {
+        let (layout, graph) = g.into_layout_graph();
+        MrfBuilder {
+            graph: Some(graph),
+            layout: Some(layout),
             potentials: self.potentials,
+            bindings: self.bindings,
             _marker: PhantomData,
         }
     }
@@ -56,10 +93,33 @@ impl<L: Label> MrfBuilder<L, Provided, Missing> {
         -> MrfBuilder<L, Provided, Provided> 
     {
         let mut potentials = self.potentials;
+        let mut bindings = self.bindings;
+        potentials.push(Box::new(p));
+        bindings.push(None);
+        MrfBuilder {
+            graph: self.graph,
+            layout: self.layout,
+            potentials,
+            bindings,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Applies a potential only to the given cliques instead of every clique of its order
+    pub fn potential_on(
+        self,
+        p: impl CliquePotential<L> + 'static,
+        cliques: impl IntoIterator<Item = Clique>,
+    ) -> MrfBuilder<L, Provided, Provided> {
+        let mut potentials = self.potentials;
+        let mut bindings = self.bindings;
         potentials.push(Box::new(p));
+        bindings.push(Some(cliques.into_iter().collect()));
         MrfBuilder {
             graph: self.graph,
+            layout: self.layout,
             potentials,
+            bindings,
             _marker: PhantomData,
         }
     }
@@ -71,10 +131,33 @@ impl<L: Label> MrfBuilder<L, Provided, Provided> {
         -> MrfBuilder<L, Provided, Provided> 
     {
         let mut potentials = self.potentials;
+        let mut bindings = self.bindings;
         potentials.push(Box::new(p));
+        bindings.push(None);
         MrfBuilder {
             graph: self.graph,
+            layout: self.layout,
             potentials,
+            bindings,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Applies a potential only to the given cliques instead of every clique of its order
+    pub fn potential_on(
+        self,
+        p: impl CliquePotential<L> + 'static,
+        cliques: impl IntoIterator<Item = Clique>,
+    ) -> MrfBuilder<L, Provided, Provided> {
+        let mut potentials = self.potentials;
+        let mut bindings = self.bindings;
+        potentials.push(Box::new(p));
+        bindings.push(Some(cliques.into_iter().collect()));
+        MrfBuilder {
+            graph: self.graph,
+            layout: self.layout,
+            potentials,
+            bindings,
             _marker: PhantomData,
         }
     }
@@ -82,17 +165,62 @@ impl<L: Label> MrfBuilder<L, Provided, Provided> {
 
 // build requires all three
 impl<L: Label> MrfBuilder<L, Provided, Provided> {
+    /// Assigns every potential its clique instances.
+    /// A potential added with `potential` of order k scores each k-node clique of
+    /// the graph exactly once: every k-subset of a maximal clique, deduplicated.
+    /// Order 1 covers every node. A potential added with `potential_on` scores
+    /// only its bound cliques, with states given in ascending node order.
     pub fn build(self) -> Result<MRF<L>, MrfError> {
-        Ok(MRF {
-            graph: self.graph.unwrap(),
-            potentials: self.potentials,
-        })
+        let graph = self.graph.unwrap();
+        let num_nodes = graph.num_nodes();
+
+        let mut by_order: HashMap<usize, Vec<Clique>> = HashMap::new();
+        let mut scopes: Vec<Vec<Clique>> = Vec::with_capacity(self.potentials.len());
+        for (p, binding) in self.potentials.iter().zip(self.bindings) {
+            let order = p.order();
+            let cliques = match binding {
+                Some(cliques) => {
+                    for clique in &cliques {
+                        if clique.len() != order {
+                            return Err(MrfError::DimensionMismatch { expected: order, got: clique.len() });
+                        }
+                        if let Some(&node) = clique.members().iter().find(|&&n| n >= num_nodes) {
+                            return Err(MrfError::NodeOutOfRange { node, num_nodes });
+                        }
+                    }
+                    cliques
+                }
+                None => by_order.entry(order)
+                    .or_insert_with(|| graph.cliques_of_order(order))
+                    .clone(),
+            };
+            scopes.push(cliques);
+        }
+
+        Ok(MRF::from_scopes(graph, self.layout, self.potentials, scopes))
     }
 }
 
+/// A Markov random field: a graph of labelled nodes scored by clique potentials.
+///
+/// The energy of a labelling is the sum of every potential's score over the
+/// clique instances assigned to it at build time (see `MrfBuilder::build`).
+/// `energy` and `node_energy` read the same assignment, so changing one node
+/// changes `energy()` by exactly the change in its `node_energy`.
+/// The graph's edges must not be modified after building.
+///
+/// Nodes can be clamped to observed labels. Samplers and optimizers never change
+/// a clamped node, but its cliques are still scored, so it acts as evidence.
 pub struct MRF<L> {
     graph: Graph<L>,
+    layout: Option<Layout>,
     potentials: Vec<Box<dyn CliquePotential<L>>>,
+    /// Clique instances scored by each potential, aligned with `potentials`
+    scopes: Vec<Vec<Clique>>,
+    /// (potential, instance) pairs whose clique contains each node
+    node_scopes: Vec<Vec<(usize, usize)>>,
+    /// Nodes fixed to their current label
+    clamped: Vec<bool>,
 }
  
 pub type SweepCallback<L> = Box<dyn FnMut(usize, &[L])>;
@@ -107,12 +235,44 @@ impl<L: Label> MRF<L> {
     pub fn builder() -> MrfBuilderInit {
         MrfBuilderInit
     }
+
+    /// Model over already validated clique instances, one list per potential
+    pub(crate) fn from_scopes(
+        graph: Graph<L>,
+        layout: Option<Layout>,
+        potentials: Vec<Box<dyn CliquePotential<L>>>,
+        scopes: Vec<Vec<Clique>>,
+    ) -> Self {
+        let num_nodes = graph.num_nodes();
+        let mut node_scopes = vec![Vec::new(); num_nodes];
+        for (pi, cliques) in scopes.iter().enumerate() {
+            for (ci, clique) in cliques.iter().enumerate() {
+                for &node in clique.members() {
+                    node_scopes[node].push((pi, ci));
+                }
+            }
+        }
+
+        MRF {
+            graph,
+            layout,
+            potentials,
+            scopes,
+            node_scopes,
+            clamped: vec![false; num_nodes],
+        }
+    }
+
     pub fn graph_mut(&mut self) -> &mut Graph<L> {
         &mut self.graph
     }
     pub fn graph(&self) -> &Graph<L> {
         &self.graph
     }
+    /// Lattice layout, if the model was built from a grid, volume or lattice
+    pub fn layout(&self) -> Option<&Layout> {
+        self.layout.as_ref()
+    }
     pub fn potentials(&self) -> &[Box<dyn CliquePotential<L>>] { 
         &self.potentials 
     }
@@ -121,36 +281,101 @@ impl<L: Label> MRF<L> {
         self.graph.num_nodes() 
     }
 
+    /// Current label of every node
+    pub fn labels(&self) -> Vec<L> {
+        (0..self.num_nodes()).map(|i| self.graph.get_node(i).state().clone()).collect()
+    }
+
+    /// Sets the label of every free node; clamped nodes keep their label
+    pub fn set_labels(&mut self, labels: &[L]) -> Result<(), MrfError> {
+        if labels.len() != self.num_nodes() {
+            return Err(MrfError::DimensionMismatch { expected: self.num_nodes(), got: labels.len() });
+        }
+        for (i, label) in labels.iter().enumerate() {
+            if !self.clamped[i] {
+                self.graph.get_node_mut(i).set_state(label.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Fixes a node to an observed label
+    pub fn clamp(&mut self, node: usize, label: L) -> Result<(), MrfError> {
+        if node >= self.num_nodes() {
+            return Err(MrfError::NodeOutOfRange { node, num_nodes: self.num_nodes() });
+        }
+        self.graph.get_node_mut(node).set_state(label);
+        self.clamped[node] = true;
+        Ok(())
+    }
+
+    /// Lets a clamped node change label again, starting from its clamped label
+    pub fn unclamp(&mut self, node: usize) -> Result<(), MrfError> {
+        if node >= self.num_nodes() {
+            return Err(MrfError::NodeOutOfRange { node, num_nodes: self.num_nodes() });
+        }
+        self.clamped[node] = false;
+        Ok(())
+    }
+
+    pub fn clear_clamps(&mut self) {
+        self.clamped.iter_mut().for_each(|c| *c = false);
+    }
+
+    pub fn is_clamped(&self, node: usize) -> bool {
+        self.clamped[node]
+    }
+
+    /// Nodes samplers and optimizers may relabel, in index order
+    pub fn free_nodes(&self) -> Vec<usize> {
+        (0..self.num_nodes()).filter(|&i| !self.clamped[i]).collect()
+    }
+
+    /// Clique instances scored by a potential
+    pub fn potential_cliques(&self, potential: usize) -> &[Clique] {
+        &self.scopes[potential]
+    }
+
+    /// (potential, instance) pairs whose clique contains a node
+    pub fn node_cliques(&self, node: usize) -> &[(usize, usize)] {
+        &self.node_scopes[node]
+    }
+
     /// Total energy of the current configuration
     pub fn energy(&self) -> f64 {
         let mut total = 0.0;
-        for p in &self.potentials {
-            let order = p.order();
-            for clique in self.graph.cliques_of_order(order) {
-                let states: Vec<L> = clique.members().iter()
-                    .map(|&i| self.graph.get_node(i).state().clone())
-                    .collect();
-                total += p.score(&states);
+        for (p, cliques) in self.potentials.iter().zip(&self.scopes) {
+            for clique in cliques {
+                total += self.clique_energy(p.as_ref(), clique, None);
             }
         }
         total
     }
 
+    /// Score of one clique instance, optionally with one node's label replaced
+    fn clique_energy(
+        &self,
+        p: &dyn CliquePotential<L>,
+        clique: &Clique,
+        override_state: Option<(usize, &L)>,
+    ) -> f64 {
+        let states: SmallVec<[L; 4]> = clique.members().iter()
+            .map(|&i| match override_state {
+                Some((node, s)) if node == i => s.clone(),
+                _ => self.graph.get_node(i).state().clone(),
+            })
+            .collect();
+        p.score_at(clique.members(), &states)
+    }
+
     /// Energy contribution from cliques involving a specific node (with optional testing of different label)
     fn node_energy_inner(&self, node: usize, override_state: Option<&L>) -> f64 {
-        let mut total = 0.0;
-        for p in &self.potentials {
-            self.graph.for_cliques_containing(node, Some(p.order()), |clique| {
-                let states: Vec<L> = clique.members().iter()
-                    .map(|&i| {
-                        if let (true, Some(s)) = (i == node, override_state) { return s.clone(); }
-                        self.graph.get_node(i).state().clone()
-                    })
-                    .collect();
-                total += p.score(&states);
-            });
-        }
-        total
+        let override_state = override_state.map(|s| (node, s));
+        self.node_scopes[node].iter()
+            .map(|&(pi, ci)| {
+                self.clique_energy(self.potentials[pi].as_ref(), &self.scopes[pi][ci], override_state)
+            })
+            .sum()
     }
     
     /// Current energy of given node
@@ -339,6 +564,240 @@ mod tests {
         assert!((actual - 2.0 * log_score(1.0)).abs() < 1e-9);
     }
 
+    // --- Unary potentials ---
+
+    fn test_unary() -> crate::potentials::UnaryPotential {
+        // Node i prefers label i % 3
+        let scores = (0..16)
+            .map(|i| (0..3).map(|l| if l == i % 3 { 0.0 } else { 1.0 }).collect())
+            .collect();
+        crate::potentials::UnaryPotential::new(scores).unwrap()
+    }
+
+    #[test]
+    fn unary_contributes_to_energy() {
+        let mut g = test_graph();
+        for i in 0..g.num_nodes() {
+            g.get_node_mut(i).set_state(0);
+        }
+        let mrf = MRF::<usize>::builder()
+            .graph(g)
+            .potential(test_pairwise())
+            .potential(test_unary())
+            .build()
+            .unwrap();
+
+        // Nodes 0, 3, 6, 9, 12, 15 prefer label 0; the other 10 pay 1.0
+        let expected = 24.0 * log_score(1.0) + 10.0;
+        assert!((mrf.energy() - expected).abs() < 1e-9);
+
+        // Node 1 prefers label 1: unary drops by 1.0, pairwise changes on 3 edges
+        let delta = mrf.node_energy_with(1, &1) - mrf.node_energy(1);
+        let expected = -1.0 + 3.0 * (log_score(0.5) - log_score(1.0));
+        assert!((delta - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn shared_unary_table_applies_to_every_node() {
+        let mut g = test_graph();
+        for i in 0..g.num_nodes() {
+            g.get_node_mut(i).set_state(1);
+        }
+        let mrf = MRF::<usize>::builder()
+            .graph(g)
+            .potential(TablePotential::unary(vec![0.0, 2.0, 5.0]))
+            .build()
+            .unwrap();
+        assert!((mrf.energy() - 32.0).abs() < 1e-9);
+        assert!((mrf.node_energy(5) - 2.0).abs() < 1e-9);
+        assert!((mrf.node_energy_with(5, &2) - 5.0).abs() < 1e-9);
+    }
+
+    // --- Clique assignment shared by energy and node_energy ---
+
+    fn triangle_graph() -> Graph<usize> {
+        // Two triangles sharing edge 1-2, plus a pendant edge 3-4
+        let mut g = Graph::new(5);
+        g.add_edge(0, 1);
+        g.add_edge(0, 2);
+        g.add_edge(1, 2);
+        g.add_edge(1, 3);
+        g.add_edge(2, 3);
+        g.add_edge(3, 4);
+        g.detect_cliques();
+        g
+    }
+
+    #[test]
+    fn pairwise_covers_edges_inside_triangles() {
+        let mrf = MRF::<usize>::builder()
+            .graph(triangle_graph())
+            .potential(test_pairwise())
+            .build()
+            .unwrap();
+        // Every edge is scored once, including the shared 1-2 edge
+        assert_eq!(mrf.potential_cliques(0).len(), 6);
+        assert_eq!(mrf.node_cliques(1).len(), 3);
+        assert_eq!(mrf.node_cliques(4).len(), 1);
+    }
+
+    #[test]
+    fn node_energy_deltas_sum_to_energy_change() {
+        let ternary = TablePotential::new((0..27).map(|i| (i as f64 * 0.37).sin()).collect(), 3, 3)
+            .unwrap();
+        let mut mrf = MRF::<usize>::builder()
+            .graph(triangle_graph())
+            .potential(test_pairwise())
+            .potential(ternary)
+            .potential(TablePotential::unary(vec![0.2, -0.4, 0.9]))
+            .build()
+            .unwrap();
+
+        let start = mrf.energy();
+        let mut deltas = 0.0;
+        for (node, label) in [(0, 1), (1, 2), (2, 2), (3, 1), (4, 2), (1, 0), (3, 0)] {
+            deltas += mrf.node_energy_with(node, &label) - mrf.node_energy(node);
+            mrf.graph_mut().get_node_mut(node).set_state(label);
+        }
+        assert!((mrf.energy() - start - deltas).abs() < 1e-9);
+    }
+
+    // --- Clique-specific bindings ---
+
+    #[test]
+    fn bound_potentials_only_score_their_cliques() {
+        use crate::graph::{Grid2D, Four};
+
+        let mut grid: Grid2D<usize> = Grid2D::new(3, 3, Four);
+        let horizontal = grid.edges_along(1, 0);
+        let vertical = grid.edges_along(0, 1);
+        assert_eq!(horizontal.len(), 6);
+        assert_eq!(vertical.len(), 6);
+        // Column x = 1 takes label 1, everything else 0
+        for y in 0..3 {
+            let i = grid.index(1, y);
+            grid.graph_mut().get_node_mut(i).set_state(1);
+        }
+
+        let h = TablePotential::new(vec![0.0, 1.0, 1.0, 0.0], 2, 2).unwrap();
+        let v = TablePotential::new(vec![0.0, 5.0, 5.0, 0.0], 2, 2).unwrap();
+        let mut mrf = MRF::<usize>::builder()
+            .grid(grid)
+            .potential_on(h, horizontal)
+            .potential_on(v, vertical)
+            .build()
+            .unwrap();
+
+        // Vertical stripe only cuts horizontal edges: 2 per row
+        assert!((mrf.energy() - 6.0).abs() < 1e-9);
+        // Centre node sits on 2 horizontal and 2 vertical edges
+        assert_eq!(mrf.node_cliques(4).len(), 4);
+        let delta = mrf.node_energy_with(4, &0) - mrf.node_energy(4);
+        assert!((delta - (-2.0 + 10.0)).abs() < 1e-9);
+        mrf.graph_mut().get_node_mut(4).set_state(0);
+        assert!((mrf.energy() - 14.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn bound_clique_order_must_match() {
+        let result = MRF::<usize>::builder()
+            .graph(test_graph())
+            .potential_on(test_pairwise(), vec![Clique::new(vec![0, 1, 2])])
+            .build();
+        assert!(matches!(result, Err(MrfError::DimensionMismatch { expected: 2, got: 3 })));
+    }
+
+    #[test]
+    fn bound_clique_nodes_must_exist() {
+        let result = MRF::<usize>::builder()
+            .graph(test_graph())
+            .potential_on(test_pairwise(), vec![Clique::new(vec![3, 16])])
+            .build();
+        assert!(matches!(result, Err(MrfError::NodeOutOfRange { node: 16, num_nodes: 16 })));
+    }
+
+    // --- Grid route ---
+
+    #[test]
+    fn build_from_grid_keeps_layout() {
+        use crate::graph::{Grid2D, Four};
+
+        let mut grid: Grid2D<usize> = Grid2D::new(4, 4, Four);
+        let corner = grid.index(3, 3);
+        grid.graph_mut().get_node_mut(corner).set_state(1);
+
+        let mrf = MRF::<usize>::builder()
+            .grid(grid)
+            .potential(test_pairwise())
+            .build()
+            .unwrap();
+
+        let layout = mrf.layout().and_then(Layout::as_grid).unwrap();
+        assert_eq!(layout.shape(), (4, 4));
+        assert_eq!(layout.coords(corner), (3, 3));
+        assert_eq!(*mrf.graph().get_node(corner).state(), 1);
+
+        // Same 24 edges as the hand-built 4x4 grid
+        let expected = 2.0 * log_score(0.5) + 22.0 * log_score(1.0);
+        assert!((mrf.energy() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn patch_cliques_score_each_block() {
+        use crate::graph::{Four, Grid2D};
+
+        // Penalise 2x2 blocks by how many labels differ from the top-left pixel
+        let scores = (0..16usize)
+            .map(|i| (0..3).filter(|b| (i >> b) & 1 != i >> 3).count() as f64 * 0.4)
+            .collect();
+        let block = TablePotential::new(scores, 2, 4).unwrap();
+        let grid: Grid2D<usize> = Grid2D::new(3, 3, Four);
+        let patches = grid.patches(2);
+        let mut mrf = MRF::<usize>::builder().grid(grid).potential_on(block, patches).build().unwrap();
+        assert_eq!(mrf.potential_cliques(0).len(), 4);
+
+        // Only the top-left pixel is 1: it differs from the three others in its block
+        let mut labels = vec![0; 9];
+        labels[0] = 1;
+        mrf.set_labels(&labels).unwrap();
+        assert!((mrf.energy() - 1.2).abs() < EPSILON);
+        // The centre pixel is in all four blocks, top-left only of the last
+        labels = vec![0; 9];
+        labels[4] = 1;
+        mrf.set_labels(&labels).unwrap();
+        assert!((mrf.energy() - (3.0 * 0.4 + 1.2)).abs() < EPSILON);
+    }
+
+    #[test]
+    fn build_from_volume_and_lattice() {
+        use crate::graph::{Grid3D, Lattice, Six, Stencil};
+
+        let volume: Grid3D<usize> = Grid3D::new(3, 3, 2, Six);
+        let voxel = volume.index(2, 1, 1);
+        let mrf = MRF::<usize>::builder().grid(volume).potential(test_pairwise()).build().unwrap();
+        assert_eq!(mrf.num_nodes(), 18);
+        assert_eq!(mrf.potential_cliques(0).len(), 33);
+        let layout = mrf.layout().and_then(Layout::as_volume).unwrap();
+        assert_eq!(layout.coords(voxel), (2, 1, 1));
+        assert!(mrf.layout().unwrap().as_grid().is_none());
+
+        let lattice: Lattice<usize> = Lattice::new(&[2, 2, 2, 2], &Stencil::von_neumann(4)).unwrap();
+        let mrf = MRF::<usize>::builder().grid(lattice).potential(test_pairwise()).build().unwrap();
+        assert_eq!(mrf.potential_cliques(0).len(), 32);
+        assert_eq!(mrf.layout().unwrap().num_nodes(), 16);
+        assert_eq!(mrf.layout().and_then(Layout::as_lattice).unwrap().coords(15), vec![1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn build_from_graph_has_no_layout() {
+        let mrf = MRF::<usize>::builder()
+            .graph(test_graph())
+            .potential(test_pairwise())
+            .build()
+            .unwrap();
+        assert!(mrf.layout().is_none());
+    }
+
     // --- Graph clique detection sanity ---
 
     #[test]
@@ -349,4 +808,28 @@ mod tests {
         }
         assert_eq!(g.maximal_cliques().len(), 24);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn clamped_nodes_keep_labels() {
+        let mut mrf = MRF::<usize>::builder().graph(test_graph()).potential(test_pairwise()).build().unwrap();
+        mrf.clamp(3, 1).unwrap();
+        assert!(mrf.is_clamped(3));
+        assert_eq!(*mrf.graph().get_node(3).state(), 1);
+        assert_eq!(mrf.free_nodes().len(), mrf.num_nodes() - 1);
+        assert!(!mrf.free_nodes().contains(&3));
+
+        mrf.set_labels(&vec![0; mrf.num_nodes()]).unwrap();
+        assert_eq!(*mrf.graph().get_node(3).state(), 1);
+        assert_eq!(*mrf.graph().get_node(4).state(), 0);
+
+        mrf.unclamp(3).unwrap();
+        mrf.set_labels(&vec![0; mrf.num_nodes()]).unwrap();
+        assert_eq!(*mrf.graph().get_node(3).state(), 0);
+
+        mrf.clamp(0, 1).unwrap();
+        mrf.clear_clamps();
+        assert_eq!(mrf.free_nodes().len(), mrf.num_nodes());
+        assert!(matches!(mrf.clamp(100, 0), Err(MrfError::NodeOutOfRange { node: 100, .. })));
+        assert!(matches!(mrf.unclamp(100), Err(MrfError::NodeOutOfRange { node: 100, num_nodes: 16 })));
+    }
+}
@@ -1,6 +1,8 @@
 mod table;
+mod unary;
 
 pub use table::*;
+pub use unary::*;
 
 use crate::state::Label;
 
@@ -11,4 +13,18 @@ pub trait HasShape {
 pub trait CliquePotential<L: Label>: Send + Sync {
     fn order(&self) -> usize;
     fn score(&self, states: &[L]) -> f64;
+
+    /// Score of the labels on a specific clique, given its member nodes.
+    /// Location-dependent potentials (e.g. per-node data terms) override this;
+    /// by default the members are ignored.
+    fn score_at(&self, members: &[usize], states: &[L]) -> f64 {
+        let _ = members;
+        self.score(states)
+    }
+
+    /// Size of the label space the potential is defined over, if it has a fixed one.
+    /// Discrete inference checks it against its own label count.
+    fn num_labels(&self) -> Option<usize> {
+        None
+    }
 }
\ No newline at end of file
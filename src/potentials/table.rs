@@ -83,6 +83,8 @@ impl TablePotential {
 impl CliquePotential<usize> for TablePotential {
     fn order(&self) -> usize { self.order }
 
+    fn num_labels(&self) -> Option<usize> { Some(self.num_labels) }
+
     fn score(&self, states: &[usize]) -> f64 {
         debug_assert_eq!(states.len(), self.order);
         self.scores[self.flat_index(states)]
@@ -0,0 +1,135 @@
+use crate::potentials::CliquePotential;
+use crate::error::MrfError;
+
+/// A node-indexed unary (data) potential.
+/// Every node carries its own score per label, stored row-major as
+/// scores[node * num_labels + label].
+pub struct UnaryPotential {
+    scores: Vec<f64>,
+    num_labels: usize,
+    num_nodes: usize,
+}
+
+impl UnaryPotential {
+    /// Build from one score vector per node. All rows must have the same length.
+    pub fn new(scores: Vec<Vec<f64>>) -> Result<Self, MrfError> {
+        let num_labels = scores.first().map_or(0, |row| row.len());
+        if num_labels == 0 {
+            return Err(MrfError::EmptyStateSpace);
+        }
+        let num_nodes = scores.len();
+        let mut flat = Vec::with_capacity(num_nodes * num_labels);
+        for row in scores {
+            if row.len() != num_labels {
+                return Err(MrfError::DimensionMismatch {
+                    expected: num_labels,
+                    got: row.len(),
+                });
+            }
+            flat.extend(row);
+        }
+        Ok(Self { scores: flat, num_labels, num_nodes })
+    }
+
+    /// Build from a flat vec of scores.
+    /// Length must be a non-zero multiple of num_labels.
+    pub fn from_flat(scores: Vec<f64>, num_labels: usize) -> Result<Self, MrfError> {
+        if num_labels == 0 {
+            return Err(MrfError::EmptyStateSpace);
+        }
+        if scores.is_empty() || !scores.len().is_multiple_of(num_labels) {
+            return Err(MrfError::DimensionMismatch {
+                expected: scores.len().div_ceil(num_labels).max(1) * num_labels,
+                got: scores.len(),
+            });
+        }
+        Ok(Self { num_nodes: scores.len() / num_labels, scores, num_labels })
+    }
+
+    pub fn num_labels(&self) -> usize { self.num_labels }
+    pub fn num_nodes(&self) -> usize { self.num_nodes }
+
+    /// Score of `label` at `node`
+    pub fn node_score(&self, node: usize, label: usize) -> f64 {
+        debug_assert!(label < self.num_labels);
+        self.scores[node * self.num_labels + label]
+    }
+
+    /// All label scores of `node`
+    pub fn node_scores(&self, node: usize) -> &[f64] {
+        &self.scores[node * self.num_labels..(node + 1) * self.num_labels]
+    }
+}
+
+impl CliquePotential<usize> for UnaryPotential {
+    fn order(&self) -> usize { 1 }
+
+    fn num_labels(&self) -> Option<usize> { Some(self.num_labels) }
+
+    /// Node-indexed scores have no location-free value; the MRF and every
+    /// inference engine call `score_at`.
+    ///
+    /// # Panics
+    ///
+    /// Always, rather than report some node's score for an unknown node.
+    fn score(&self, _states: &[usize]) -> f64 {
+        panic!("UnaryPotential is node-indexed and must be scored with score_at")
+    }
+
+    fn score_at(&self, members: &[usize], states: &[usize]) -> f64 {
+        debug_assert_eq!(members.len(), 1);
+        debug_assert_eq!(states.len(), 1);
+        self.node_score(members[0], states[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_valid() {
+        let up = UnaryPotential::new(vec![vec![0.0, 1.0], vec![2.0, 3.0], vec![4.0, 5.0]]).unwrap();
+        assert_eq!(up.num_nodes(), 3);
+        assert_eq!(up.num_labels(), 2);
+        assert_eq!(up.order(), 1);
+    }
+
+    #[test]
+    fn new_empty_fails() {
+        assert!(matches!(UnaryPotential::new(vec![]), Err(MrfError::EmptyStateSpace)));
+        assert!(matches!(UnaryPotential::new(vec![vec![]]), Err(MrfError::EmptyStateSpace)));
+    }
+
+    #[test]
+    fn new_ragged_fails() {
+        assert!(matches!(
+            UnaryPotential::new(vec![vec![0.0, 1.0], vec![2.0]]),
+            Err(MrfError::DimensionMismatch { expected: 2, got: 1 })
+        ));
+    }
+
+    #[test]
+    fn from_flat_matches_new() {
+        let a = UnaryPotential::from_flat(vec![0.0, 1.0, 2.0, 3.0], 2).unwrap();
+        let b = UnaryPotential::new(vec![vec![0.0, 1.0], vec![2.0, 3.0]]).unwrap();
+        assert_eq!(a.node_scores(1), b.node_scores(1));
+        assert!(UnaryPotential::from_flat(vec![0.0; 5], 2).is_err());
+        assert!(UnaryPotential::from_flat(vec![], 2).is_err());
+    }
+
+    #[test]
+    fn score_at_is_per_node() {
+        let up = UnaryPotential::new(vec![vec![0.0, 1.0], vec![2.0, 3.0]]).unwrap();
+        assert!((up.score_at(&[0], &[1]) - 1.0).abs() < 1e-12);
+        assert!((up.score_at(&[1], &[0]) - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    #[should_panic(expected = "score_at")]
+    fn location_free_score_panics() {
+        let up = UnaryPotential::new(vec![vec![0.0, 1.0], vec![2.0, 3.0]]).unwrap();
+        let p: &dyn CliquePotential<usize> = &up;
+        p.score(&[1]);
+    }
+}
@@ -16,6 +16,7 @@ impl Annealer for ConstantAnnealer {
     }
 }
 
+/// `initial - rate * step`, floored at `min_temperature`
 pub struct LinearAnnealer {
     initial: f64,
     rate: f64,
@@ -34,6 +35,7 @@ impl Annealer for LinearAnnealer {
     }
 }
 
+/// `initial * rate^step`, floored at `min_temperature`
 pub struct ExponentialAnnealer {
     initial: f64,
     rate: f64,
@@ -52,6 +54,7 @@ impl Annealer for ExponentialAnnealer {
     }
 }
 
+/// `initial / (1 + ln(1 + step))`, floored at `min_temperature`
 pub struct LogarithmicAnnealer {
     initial: f64,
     min_temperature: f64
@@ -0,0 +1,229 @@
+use std::thread;
+use crate::state::Label;
+use crate::MRF;
+use crate::samplers::{Annealer, Proposal, RunOptions};
+use crate::samplers::cluster::sample_log_weights;
+use crate::error::MrfError;
+use rand::Rng;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// Greedy colouring of the free nodes in index order such that no two nodes of the
+/// same colour share a clique instance. Nodes of one colour are conditionally independent.
+pub(crate) fn color_classes<L: Label>(mrf: &MRF<L>) -> Vec<Vec<usize>> {
+    let n = mrf.num_nodes();
+    let mut color = vec![usize::MAX; n];
+    let mut classes: Vec<Vec<usize>> = Vec::new();
+    let mut taken: Vec<bool> = Vec::new();
+    for node in mrf.free_nodes() {
+        taken.iter_mut().for_each(|t| *t = false);
+        for &(pi, ci) in mrf.node_cliques(node) {
+            for &other in mrf.potential_cliques(pi)[ci].members() {
+                if color[other] != usize::MAX {
+                    taken[color[other]] = true;
+                }
+            }
+        }
+        let c = taken.iter().position(|&t| !t).unwrap_or_else(|| {
+            taken.push(false);
+            classes.push(Vec::new());
+            classes.len() - 1
+        });
+        color[node] = c;
+        classes[c].push(node);
+    }
+    classes
+}
+
+/// Gibbs sampler that updates one colour class at a time, in parallel.
+///
+/// The graph is coloured so that no two same-coloured nodes share a clique,
+/// which makes each class conditionally independent given the others. Each
+/// class is split into `threads` contiguous chunks, and chunk k is always
+/// sampled with RNG stream k derived from `RunOptions::seed`, so a run is
+/// reproducible for a given seed and thread count.
+pub struct ChromaticGibbsSampler<A: Annealer> {
+    sweeps: usize,
+    annealer: A,
+    threads: usize,
+}
+
+impl<A: Annealer> ChromaticGibbsSampler<A> {
+    pub fn new(sweeps: usize, annealer: A, threads: usize) -> Self {
+        Self { sweeps, annealer, threads }
+    }
+
+    pub fn sweeps(&self) -> usize {
+        self.sweeps
+    }
+
+    pub fn annealer(&self) -> &A {
+        &self.annealer
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    fn sweep<L: Label>(
+        &self,
+        temperature: f64,
+        mrf: &mut MRF<L>,
+        classes: &[Vec<usize>],
+        proposal: &(impl Proposal<L> + Sync),
+        streams: &mut [StdRng],
+    ) {
+        for class in classes {
+            let chunk = class.len().div_ceil(streams.len()).max(1);
+            let shared: &MRF<L> = mrf;
+            let updates: Vec<Vec<(usize, L)>> = thread::scope(|s| {
+                let handles: Vec<_> = class.chunks(chunk).zip(streams.iter_mut())
+                    .map(|(nodes, rng)| s.spawn(move || {
+                        nodes.iter()
+                            .map(|&node| (node, sample_node(shared, node, temperature, proposal, rng)))
+                            .collect()
+                    }))
+                    .collect();
+                handles.into_iter().map(|h| h.join().unwrap()).collect()
+            });
+            for (node, label) in updates.into_iter().flatten() {
+                mrf.graph_mut().get_node_mut(node).set_state(label);
+            }
+        }
+    }
+
+    pub fn run<L: Label>(
+        &self,
+        mrf: &mut MRF<L>,
+        proposal: &(impl Proposal<L> + Sync),
+        opts: RunOptions,
+    ) -> Result<(), MrfError> {
+        self.run_with(mrf, proposal, opts, |_, _| {})
+    }
+
+    pub fn run_with<L: Label>(
+        &self,
+        mrf: &mut MRF<L>,
+        proposal: &(impl Proposal<L> + Sync),
+        opts: RunOptions,
+        mut on_sweep: impl FnMut(usize, &MRF<L>),
+    ) -> Result<(), MrfError> {
+        if self.threads == 0 {
+            return Err(MrfError::InvalidParameter { name: "threads", value: 0.0 });
+        }
+        let mut master = match opts.seed {
+            Some(s) => StdRng::seed_from_u64(s),
+            None => StdRng::from_rng(&mut rand::rng()),
+        };
+        let mut streams: Vec<StdRng> = (0..self.threads).map(|_| StdRng::from_rng(&mut master)).collect();
+        let classes = color_classes(mrf);
+
+        on_sweep(0, mrf);
+        for i in 0..self.sweeps {
+            let temp = self.annealer.temperature(i);
+            self.sweep(temp, mrf, &classes, proposal, &mut streams);
+            on_sweep(i + 1, mrf);
+        }
+        Ok(())
+    }
+}
+
+/// Draws a node's label from its conditional given all other nodes
+fn sample_node<L: Label>(
+    mrf: &MRF<L>,
+    node: usize,
+    temperature: f64,
+    proposal: &impl Proposal<L>,
+    rng: &mut impl Rng,
+) -> L {
+    let current = mrf.graph().get_node(node).state();
+    let mut candidates = proposal.candidates(current, rng);
+    let log_scores: Vec<f64> = candidates.iter()
+        .map(|c| -mrf.node_energy_with(node, c) / temperature)
+        .collect();
+    let k = sample_log_weights(&log_scores, rng);
+    candidates.swap_remove(k)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Eight, Four, Grid2D};
+    use crate::inference::ExactInference;
+    use crate::potentials::{TablePotential, UnaryPotential};
+    use crate::samplers::{ConstantAnnealer, DiscreteProposal};
+
+    fn model(width: usize, height: usize) -> MRF<usize> {
+        let grid: Grid2D<usize> = Grid2D::new(width, height, Four);
+        let n = width * height;
+        let potts = TablePotential::new(vec![0.0, 0.6, 0.6, 0.0], 2, 2).unwrap();
+        let field = UnaryPotential::new((0..n).map(|i| vec![0.0, ((i * 5) % 7) as f64 * 0.2 - 0.6]).collect()).unwrap();
+        MRF::<usize>::builder().grid(grid).potential(potts).potential(field).build().unwrap()
+    }
+
+    fn labels(mrf: &MRF<usize>) -> Vec<usize> {
+        (0..mrf.num_nodes()).map(|i| *mrf.graph().get_node(i).state()).collect()
+    }
+
+    #[test]
+    fn coloring_separates_clique_members() {
+        let grid: Grid2D<usize> = Grid2D::new(5, 5, Eight);
+        let potts = TablePotential::new(vec![0.0, 1.0, 1.0, 0.0], 2, 2).unwrap();
+        let mrf = MRF::<usize>::builder().grid(grid).potential(potts).build().unwrap();
+        let classes = color_classes(&mrf);
+        assert_eq!(classes.len(), 4);
+        assert_eq!(classes.iter().map(|c| c.len()).sum::<usize>(), 25);
+        for class in &classes {
+            for &a in class {
+                for &b in class {
+                    assert!(a == b || !mrf.graph().get_node(a).neighbors().contains(&b));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reproducible_for_seed() {
+        let proposal = DiscreteProposal::new(2);
+        let sampler = ChromaticGibbsSampler::new(20, ConstantAnnealer::new(1.0), 3);
+        let mut a = model(12, 12);
+        let mut b = model(12, 12);
+        sampler.run(&mut a, &proposal, RunOptions { seed: Some(42) }).unwrap();
+        sampler.run(&mut b, &proposal, RunOptions { seed: Some(42) }).unwrap();
+        assert_eq!(labels(&a), labels(&b));
+    }
+
+    #[test]
+    fn matches_exact_marginals() {
+        let mut mrf = model(3, 3);
+        let exact = ExactInference::new(2, 1 << 10).run(&mrf).unwrap();
+        let sweeps = 20_000;
+        let mut ones = [0usize; 9];
+        let sampler = ChromaticGibbsSampler::new(sweeps, ConstantAnnealer::new(1.0), 2);
+        sampler.run_with(&mut mrf, &DiscreteProposal::new(2), RunOptions { seed: Some(8) }, |i, m| {
+            if i > 0 {
+                for (node, c) in ones.iter_mut().enumerate() {
+                    *c += *m.graph().get_node(node).state();
+                }
+            }
+        }).unwrap();
+        for (c, p) in ones.iter().zip(&exact.marginals) {
+            assert!((*c as f64 / sweeps as f64 - p[1]).abs() < 0.02);
+        }
+    }
+
+    #[test]
+    fn clamped_nodes_are_not_coloured() {
+        let mut mrf = model(4, 4);
+        mrf.clamp(5, 1).unwrap();
+        mrf.clamp(10, 0).unwrap();
+        let classes = color_classes(&mrf);
+        assert_eq!(classes.iter().map(|c| c.len()).sum::<usize>(), 14);
+        assert!(classes.iter().flatten().all(|&n| n != 5 && n != 10));
+
+        let sampler = ChromaticGibbsSampler::new(20, ConstantAnnealer::new(1.0), 2);
+        sampler.run(&mut mrf, &DiscreteProposal::new(2), RunOptions { seed: Some(1) }).unwrap();
+        assert_eq!(labels(&mrf)[5], 1);
+        assert_eq!(labels(&mrf)[10], 0);
+    }
+}
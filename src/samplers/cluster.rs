@@ -0,0 +1,113 @@
+use rand::{Rng, RngExt};
+use crate::MRF;
+use crate::error::MrfError;
+use crate::inference::factors::FactorTables;
+
+/// A discrete MRF viewed as a Potts model: pairwise terms that only distinguish
+/// equal from unequal labels, plus arbitrary per-node field energies.
+pub(crate) struct PottsModel {
+    pub num_labels: usize,
+    /// (i, j, J): the pair pays J >= 0 more energy when its labels differ
+    pub bonds: Vec<(usize, usize, f64)>,
+    /// Bonds touching each node as (neighbour, J)
+    pub adjacency: Vec<Vec<(usize, f64)>>,
+    /// Summed unary energy per node and label
+    pub field: Vec<Vec<f64>>,
+}
+
+impl PottsModel {
+    pub fn new(mrf: &MRF<usize>, num_labels: usize) -> Result<Self, MrfError> {
+        let tables = FactorTables::new(mrf, num_labels)?;
+        let n = mrf.num_nodes();
+        let mut bonds = Vec::new();
+        let mut adjacency = vec![Vec::new(); n];
+        let mut field = vec![vec![0.0; num_labels]; n];
+
+        for (fi, f) in tables.factors.iter().enumerate() {
+            match f.members.len() {
+                1 => {
+                    for (h, e) in field[f.members[0]].iter_mut().zip(&f.energies) {
+                        *h += e;
+                    }
+                }
+                2 => {
+                    let coupling = potts_coupling(&f.energies, num_labels)
+                        .ok_or(MrfError::NotPotts { potential: tables.owners[fi].0 })?;
+                    let (i, j) = (f.members[0], f.members[1]);
+                    if coupling > 0.0 {
+                        bonds.push((i, j, coupling));
+                        adjacency[i].push((j, coupling));
+                        adjacency[j].push((i, coupling));
+                    }
+                }
+                order => return Err(MrfError::UnsupportedOrder { order, max: 2 }),
+            }
+        }
+        Ok(Self { num_labels, bonds, adjacency, field })
+    }
+
+    /// Field energy of giving every member of a cluster the same label
+    pub fn cluster_field(&self, cluster: &[usize], label: usize) -> f64 {
+        cluster.iter().map(|&i| self.field[i][label]).sum()
+    }
+}
+
+/// J if the table is c on the diagonal and c + J (J >= 0) everywhere else
+fn potts_coupling(table: &[f64], num_labels: usize) -> Option<f64> {
+    const TOLERANCE: f64 = 1e-9;
+    let diagonal = table[0];
+    let coupling = if num_labels > 1 { table[1] - diagonal } else { 0.0 };
+    if coupling < -TOLERANCE {
+        return None;
+    }
+    for a in 0..num_labels {
+        for b in 0..num_labels {
+            let expected = if a == b { diagonal } else { diagonal + coupling };
+            if (table[a * num_labels + b] - expected).abs() > TOLERANCE {
+                return None;
+            }
+        }
+    }
+    Some(coupling.max(0.0))
+}
+
+/// Union-find over node indices
+pub(crate) struct DisjointSets {
+    parent: Vec<usize>,
+}
+
+impl DisjointSets {
+    pub fn new(n: usize) -> Self {
+        Self { parent: (0..n).collect() }
+    }
+
+    pub fn find(&mut self, mut x: usize) -> usize {
+        while self.parent[x] != x {
+            self.parent[x] = self.parent[self.parent[x]];
+            x = self.parent[x];
+        }
+        x
+    }
+
+    pub fn union(&mut self, a: usize, b: usize) {
+        let (ra, rb) = (self.find(a), self.find(b));
+        if ra != rb {
+            self.parent[ra] = rb;
+        }
+    }
+}
+
+/// Draws an index with probability proportional to exp(log_weights[k])
+pub(crate) fn sample_log_weights(log_weights: &[f64], rng: &mut impl Rng) -> usize {
+    let max_log = log_weights.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let weights: Vec<f64> = log_weights.iter().map(|&s| (s - max_log).exp()).collect();
+    let total: f64 = weights.iter().sum();
+    let mut r = rng.random_range(0.0..total);
+    for (k, &w) in weights.iter().enumerate() {
+        r -= w;
+        if r <= 0.0 {
+            return k;
+        }
+    }
+    weights.len() - 1
+}
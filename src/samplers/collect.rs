@@ -0,0 +1,99 @@
+use crate::state::Label;
+
+/// Which sweeps a sampling run keeps
+pub struct SampleOptions {
+    /// Sweeps discarded before the first kept sample
+    pub burn_in: usize,
+    /// Keep every `thin`-th sweep after burn-in
+    pub thin: usize,
+    /// Store every kept labelling, not just the histograms
+    pub keep_samples: bool,
+}
+
+impl Default for SampleOptions {
+    fn default() -> Self {
+        Self { burn_in: 0, thin: 1, keep_samples: false }
+    }
+}
+
+impl SampleOptions {
+    /// Whether the labelling after 1-based sweep `sweep` is kept
+    pub(crate) fn keeps(&self, sweep: usize) -> bool {
+        sweep > self.burn_in && (sweep - self.burn_in).is_multiple_of(self.thin)
+    }
+}
+
+/// Statistics gathered over the kept sweeps of a sampling run
+pub struct SampleResult<L> {
+    num_samples: usize,
+    histograms: Vec<Vec<(L, usize)>>,
+    map_labels: Vec<L>,
+    map_energy: f64,
+    samples: Option<Vec<Vec<L>>>,
+}
+
+impl<L: Label> SampleResult<L> {
+    pub(crate) fn new(num_nodes: usize, keep_samples: bool) -> Self {
+        Self {
+            num_samples: 0,
+            histograms: vec![Vec::new(); num_nodes],
+            map_labels: Vec::new(),
+            map_energy: f64::INFINITY,
+            samples: keep_samples.then(Vec::new),
+        }
+    }
+
+    pub(crate) fn set_map(&mut self, labels: Vec<L>, energy: f64) {
+        self.map_labels = labels;
+        self.map_energy = energy;
+    }
+
+    /// Adds a kept labelling to the histograms
+    pub(crate) fn record(&mut self, labels: &[L]) {
+        self.num_samples += 1;
+        for (hist, label) in self.histograms.iter_mut().zip(labels) {
+            match hist.iter_mut().find(|(l, _)| l == label) {
+                Some((_, count)) => *count += 1,
+                None => hist.push((label.clone(), 1)),
+            }
+        }
+        if let Some(samples) = &mut self.samples {
+            samples.push(labels.to_vec());
+        }
+    }
+
+    pub fn num_samples(&self) -> usize {
+        self.num_samples
+    }
+
+    /// (label, count) pairs seen at a node, in order of first appearance
+    pub fn histogram(&self, node: usize) -> &[(L, usize)] {
+        &self.histograms[node]
+    }
+
+    /// Empirical marginal of a node as (label, probability) pairs
+    pub fn marginal(&self, node: usize) -> Vec<(L, f64)> {
+        let n = self.num_samples.max(1) as f64;
+        self.histograms[node].iter().map(|(l, c)| (l.clone(), *c as f64 / n)).collect()
+    }
+
+    /// Empirical probability of one label at a node
+    pub fn probability(&self, node: usize, label: &L) -> f64 {
+        let n = self.num_samples.max(1) as f64;
+        self.histograms[node].iter().find(|(l, _)| l == label).map_or(0.0, |(_, c)| *c as f64 / n)
+    }
+
+    /// Lowest-energy labelling seen so far
+    pub fn map_labels(&self) -> &[L] {
+        &self.map_labels
+    }
+
+    pub fn map_energy(&self) -> f64 {
+        self.map_energy
+    }
+
+    /// Kept labellings, if `SampleOptions::keep_samples` was set
+    pub fn samples(&self) -> Option<&[Vec<L>]> {
+        self.samples.as_deref()
+    }
+}
@@ -2,17 +2,79 @@ use crate::state::Label;
 use crate::MRF;
 use crate::samplers::Annealer;
 use crate::error::MrfError;
+use chacha20::ChaCha12Rng;
 use rand::{Rng, RngExt};
-use rand::rngs::StdRng;
 use rand::{SeedableRng};
 use rand::seq::SliceRandom;
-use crate::samplers::{Proposal};
+use crate::samplers::{Proposal, RunReport, SampleOptions, SampleResult};
+use std::time::Instant;
 
 #[derive(Default)]
 pub struct RunOptions {
     pub seed: Option<u64>,
 }
 
+/// Everything needed to continue a Gibbs run exactly where it stopped: the labels,
+/// the number of sweeps done, the run's seed and position in its random stream,
+/// the tracked energy and the report so far. Clone it to keep a copy, or save the
+/// parts and rebuild it with `new` in another process; resuming repeats the same
+/// sweeps bit for bit.
+#[derive(Clone)]
+pub struct GibbsCheckpoint<L> {
+    sweep: usize,
+    labels: Vec<L>,
+    seed: u64,
+    rng_position: u128,
+    energy: f64,
+    report: RunReport<L>,
+}
+
+impl<L: Label> GibbsCheckpoint<L> {
+    /// Rebuilds a checkpoint from parts read back from its getters
+    pub fn new(
+        sweep: usize,
+        labels: Vec<L>,
+        seed: u64,
+        rng_position: u128,
+        energy: f64,
+        report: RunReport<L>,
+    ) -> Self {
+        Self { sweep, labels, seed, rng_position, energy, report }
+    }
+
+    /// Sweeps completed so far
+    pub fn sweep(&self) -> usize {
+        self.sweep
+    }
+
+    pub fn labels(&self) -> &[L] {
+        &self.labels
+    }
+
+    /// Seed of the run, drawn at random if `RunOptions::seed` was unset
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Words drawn so far from the random stream seeded by `seed`
+    pub fn rng_position(&self) -> u128 {
+        self.rng_position
+    }
+
+    /// Total energy of `labels`, tracked from per-sweep changes
+    pub fn energy(&self) -> f64 {
+        self.energy
+    }
+
+    pub fn report(&self) -> &RunReport<L> {
+        &self.report
+    }
+
+    pub fn into_report(self) -> RunReport<L> {
+        self.report
+    }
+}
+
 pub struct GibbsSampler<A: Annealer> {
     sweeps: usize,
     annealer: A,
@@ -31,80 +93,325 @@ impl<A: Annealer> GibbsSampler<A> {
         &self.annealer
     }
 
-    fn sweep<L: Label>(
+    pub fn run<L: Label>(
         &self,
-        temperature: f64,
         mrf: &mut MRF<L>,
         proposal: &impl Proposal<L>,
-        rng: &mut impl Rng,
-    ) {
-        let num_nodes = mrf.num_nodes();
-        let mut indices: Vec<usize> = (0..num_nodes).collect();
-        indices.shuffle(rng);
-
-        for &node in &indices {
-            let current = mrf.graph().get_node(node).state().clone();
-            let candidates = proposal.candidates(&current, rng);
-            let mut log_scores: Vec<f64> = Vec::with_capacity(candidates.len());
-
-            for candidate in &candidates {
-                // Temporarily set candidate to compute energy
-                mrf.graph_mut().get_node_mut(node).set_state(candidate.clone());
-                log_scores.push(-mrf.node_energy(node));
-            }
-
-            // Sample from conditional via Gibbs
-            let max_log = log_scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
-            let weights: Vec<f64> = log_scores.iter()
-                .map(|&s| ((s - max_log) / temperature).exp())
-                .collect();
-            let total: f64 = weights.iter().sum();
-            let mut r = rng.random_range(0.0..total);
-
-            for (k, &w) in weights.iter().enumerate() {
-                r -= w;
-                if r <= 0.0 {
-                    mrf.graph_mut().get_node_mut(node).set_state(candidates[k].clone());
-                    break;
-                }
-            }
-        }
+        opts: RunOptions,
+    ) -> Result<RunReport<L>, MrfError> {
+        self.run_with(mrf, proposal, opts, |_, _| {})
     }
 
-    pub fn run<L: Label>(
+    /// Runs all sweeps, discarding burn-in and keeping every `thin`-th labelling after it.
+    /// Kept labellings feed per-node label histograms; the lowest-energy labelling
+    /// of the whole run is tracked as the MAP estimate.
+    pub fn sample<L: Label>(
         &self,
         mrf: &mut MRF<L>,
         proposal: &impl Proposal<L>,
         opts: RunOptions,
-    ) -> Result<(), MrfError> {
-        let mut rng = match opts.seed {
-            Some(s) => StdRng::seed_from_u64(s),
-            None => StdRng::from_rng(&mut rand::rng()),
-        };
-        for i in 0..self.sweeps {
-            let temp = self.annealer.temperature(i);
-            self.sweep(temp, mrf, proposal, &mut rng);
+        collect: SampleOptions,
+    ) -> Result<SampleResult<L>, MrfError> {
+        if collect.thin == 0 {
+            return Err(MrfError::InvalidParameter { name: "thin", value: 0.0 });
         }
-        Ok(())
+        let mut result = SampleResult::new(mrf.num_nodes(), collect.keep_samples);
+        let report = self.run_with(mrf, proposal, opts, |i, mrf| {
+            if collect.keeps(i) {
+                result.record(&mrf.labels());
+            }
+        })?;
+        let (labels, energy) = report.into_best();
+        result.set_map(labels, energy);
+        Ok(result)
     }
-    
+
+    /// Like `run`, calling `on_sweep` with the sweep count before the first sweep and after each one.
+    /// The report's energy trace is kept up to date from per-node energy changes, so
+    /// the total energy is only computed once.
     pub fn run_with<L: Label>(
         &self,
         mrf: &mut MRF<L>,
         proposal: &impl Proposal<L>,
         opts: RunOptions,
         mut on_sweep: impl FnMut(usize, &MRF<L>),
-    ) -> Result<(), MrfError> {
-        let mut rng = match opts.seed {
-            Some(s) => StdRng::seed_from_u64(s),
-            None => StdRng::from_rng(&mut rand::rng()),
-        };
+    ) -> Result<RunReport<L>, MrfError> {
+        let mut checkpoint = self.start(mrf, opts);
         on_sweep(0, mrf);
-        for i in 0..self.sweeps {
+        self.resume(mrf, proposal, &mut checkpoint, self.sweeps, on_sweep)?;
+        Ok(checkpoint.into_report())
+    }
+
+    /// Checkpoint of a run that has not swept yet, starting from the MRF's current labels
+    pub fn start<L: Label>(&self, mrf: &MRF<L>, opts: RunOptions) -> GibbsCheckpoint<L> {
+        let seed = opts.seed.unwrap_or_else(|| rand::rng().random());
+        let labels = mrf.labels();
+        let energy = mrf.energy();
+        let report = RunReport::new(labels.clone(), energy, self.sweeps);
+        GibbsCheckpoint::new(0, labels, seed, 0, energy, report)
+    }
+
+    /// Restores the checkpoint's labels into the MRF and runs up to `sweeps` more sweeps,
+    /// stopping early at `self.sweeps()`. The checkpoint is advanced in place, so
+    /// annealing continues from its sweep index. The whole run draws from one
+    /// `ChaCha12Rng::seed_from_u64(seed)` stream, picked up at the checkpoint's
+    /// position.
+    pub fn resume<L: Label>(
+        &self,
+        mrf: &mut MRF<L>,
+        proposal: &impl Proposal<L>,
+        checkpoint: &mut GibbsCheckpoint<L>,
+        sweeps: usize,
+        mut on_sweep: impl FnMut(usize, &MRF<L>),
+    ) -> Result<(), MrfError> {
+        mrf.set_labels(&checkpoint.labels)?;
+        let end = self.sweeps.min(checkpoint.sweep.saturating_add(sweeps));
+        let mut rng = ChaCha12Rng::seed_from_u64(checkpoint.seed);
+        rng.set_word_pos(checkpoint.rng_position);
+        for i in checkpoint.sweep..end {
             let temp = self.annealer.temperature(i);
-            self.sweep(temp, mrf, proposal, &mut rng);
+            let start = Instant::now();
+            let (changes, delta) = sweep(temp, mrf, proposal, &mut rng);
+            let elapsed = start.elapsed();
+            checkpoint.energy += delta;
+            checkpoint.sweep = i + 1;
+            checkpoint.rng_position = rng.get_word_pos();
+            checkpoint.report.push(temp, checkpoint.energy, changes, elapsed, || mrf.labels());
             on_sweep(i + 1, mrf);
         }
+        checkpoint.labels = mrf.labels();
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// One Gibbs pass over all free nodes in random order at the given temperature.
+/// Returns the number of label changes and the change in total energy.
+pub(crate) fn sweep<L: Label>(
+    temperature: f64,
+    mrf: &mut MRF<L>,
+    proposal: &impl Proposal<L>,
+    rng: &mut impl Rng,
+) -> (usize, f64) {
+    let mut indices = mrf.free_nodes();
+    indices.shuffle(rng);
+    let mut changes = 0;
+    let mut delta = 0.0;
+
+    for &node in &indices {
+        let current = mrf.graph().get_node(node).state().clone();
+        let before = mrf.node_energy(node);
+        let candidates = proposal.candidates(&current, rng);
+        let mut log_scores: Vec<f64> = Vec::with_capacity(candidates.len());
+
+        for candidate in &candidates {
+            // Temporarily set candidate to compute energy
+            mrf.graph_mut().get_node_mut(node).set_state(candidate.clone());
+            log_scores.push(-mrf.node_energy(node));
+        }
+
+        // Sample from conditional via Gibbs
+        let max_log = log_scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let weights: Vec<f64> = log_scores.iter()
+            .map(|&s| ((s - max_log) / temperature).exp())
+            .collect();
+        let total: f64 = weights.iter().sum();
+        let mut r = rng.random_range(0.0..total);
+
+        for (k, &w) in weights.iter().enumerate() {
+            r -= w;
+            if r <= 0.0 {
+                if candidates[k] != current {
+                    changes += 1;
+                }
+                delta += -log_scores[k] - before;
+                mrf.graph_mut().get_node_mut(node).set_state(candidates[k].clone());
+                break;
+            }
+        }
+    }
+    (changes, delta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Four, Grid2D};
+    use crate::inference::ExactInference;
+    use crate::potentials::{TablePotential, UnaryPotential};
+    use crate::samplers::{ConstantAnnealer, DiscreteProposal, LinearAnnealer};
+
+    fn model() -> MRF<usize> {
+        let grid: Grid2D<usize> = Grid2D::new(3, 2, Four);
+        let potts = TablePotential::new(vec![0.0, 0.8, 0.8, 0.0], 2, 2).unwrap();
+        let field = UnaryPotential::new((0..6).map(|i| vec![0.0, 0.3 * i as f64 - 0.8]).collect()).unwrap();
+        MRF::<usize>::builder().grid(grid).potential(potts).potential(field).build().unwrap()
+    }
+
+    #[test]
+    fn burn_in_and_thinning() {
+        let mut mrf = model();
+        let sampler = GibbsSampler::new(100, ConstantAnnealer::new(1.0));
+        let collect = SampleOptions { burn_in: 10, thin: 3, keep_samples: true };
+        let result = sampler.sample(&mut mrf, &DiscreteProposal::new(2), RunOptions { seed: Some(1) }, collect).unwrap();
+        // Sweeps 13, 16, ..., 100
+        assert_eq!(result.num_samples(), 30);
+        assert_eq!(result.samples().unwrap().len(), 30);
+        assert_eq!(result.samples().unwrap().last().unwrap(), &mrf.labels());
+        for node in 0..6 {
+            let total: usize = result.histogram(node).iter().map(|(_, c)| c).sum();
+            assert_eq!(total, 30);
+        }
+    }
+
+    #[test]
+    fn marginals_and_map_match_exact() {
+        let mut mrf = model();
+        let exact = ExactInference::new(2, 1 << 10).run(&mrf).unwrap();
+        let sampler = GibbsSampler::new(20_000, ConstantAnnealer::new(1.0));
+        let collect = SampleOptions { burn_in: 100, ..Default::default() };
+        let result = sampler.sample(&mut mrf, &DiscreteProposal::new(2), RunOptions { seed: Some(3) }, collect).unwrap();
+        assert!(result.samples().is_none());
+        for (node, p) in exact.marginals.iter().enumerate() {
+            assert!((result.probability(node, &1) - p[1]).abs() < 0.02);
+            let total: f64 = result.marginal(node).iter().map(|(_, p)| p).sum();
+            assert!((total - 1.0).abs() < 1e-12);
+        }
+        assert_eq!(result.map_labels(), exact.map_labels.as_slice());
+        assert!((result.map_energy() - exact.map_energy).abs() < 1e-9);
+    }
+
+    #[test]
+    fn report_tracks_energy() {
+        let mut mrf = model();
+        let sampler = GibbsSampler::new(50, LinearAnnealer::new(3.0, 0.5, 0.05));
+        let mut exact = Vec::new();
+        let report = sampler.run_with(&mut mrf, &DiscreteProposal::new(2), RunOptions { seed: Some(7) }, |i, m| {
+            if i > 0 {
+                exact.push(m.energy());
+            }
+        }).unwrap();
+        assert_eq!(report.num_sweeps(), 50);
+        for (tracked, e) in report.energies().iter().zip(&exact) {
+            assert!((tracked - e).abs() < 1e-9);
+        }
+        for (i, t) in report.temperatures().iter().enumerate() {
+            assert_eq!(*t, sampler.annealer().temperature(i));
+        }
+        assert!(report.changes().iter().all(|&c| c <= 6));
+        assert!(report.changes().iter().any(|&c| c > 0));
+        assert_eq!(report.sweep_times().len(), 50);
+
+        let best = exact.iter().copied().fold(report.initial_energy(), f64::min);
+        assert!((report.best_energy() - best).abs() < 1e-9);
+        mrf.set_labels(report.best_labels()).unwrap();
+        assert!((mrf.energy() - report.best_energy()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn resumes_bit_identically() {
+        let sampler = GibbsSampler::new(40, LinearAnnealer::new(3.0, 0.2, 0.07));
+        let proposal = DiscreteProposal::new(2);
+        let mut straight = model();
+        let full = sampler.run(&mut straight, &proposal, RunOptions { seed: Some(9) }).unwrap();
+
+        let mut mrf = model();
+        let mut checkpoint = sampler.start(&mrf, RunOptions { seed: Some(9) });
+        sampler.resume(&mut mrf, &proposal, &mut checkpoint, 15, |_, _| {}).unwrap();
+        assert_eq!(checkpoint.sweep(), 15);
+        let saved = checkpoint.clone();
+        sampler.resume(&mut mrf, &proposal, &mut checkpoint, 100, |_, _| {}).unwrap();
+        assert_eq!(checkpoint.sweep(), 40);
+        assert_eq!(mrf.labels(), straight.labels());
+        assert_eq!(checkpoint.report().energies(), full.energies());
+
+        // A fresh model resumed from the saved copy repeats the same sweeps
+        let mut fresh = model();
+        let mut resumed = saved;
+        let mut seen = Vec::new();
+        sampler.resume(&mut fresh, &proposal, &mut resumed, usize::MAX, |i, _| seen.push(i)).unwrap();
+        assert_eq!(seen, (16..=40).collect::<Vec<_>>());
+        assert_eq!(fresh.labels(), straight.labels());
+        let report = resumed.into_report();
+        assert_eq!(report.energies(), full.energies());
+        assert_eq!(report.temperatures(), full.temperatures());
+        assert_eq!(report.changes(), full.changes());
+        assert_eq!(report.best_labels(), full.best_labels());
+    }
+
+    #[test]
+    fn resumes_from_saved_parts() {
+        let sampler = GibbsSampler::new(30, LinearAnnealer::new(2.0, 0.05, 0.5));
+        let proposal = DiscreteProposal::new(2);
+        let mut straight = model();
+        let full = sampler.run(&mut straight, &proposal, RunOptions { seed: Some(21) }).unwrap();
+
+        let mut mrf = model();
+        let mut checkpoint = sampler.start(&mrf, RunOptions { seed: Some(21) });
+        sampler.resume(&mut mrf, &proposal, &mut checkpoint, 12, |_, _| {}).unwrap();
+
+        // Rebuild from plain values, as a later process reading them back would
+        let r = checkpoint.report();
+        let report = RunReport::from_parts(
+            r.initial_energy(),
+            r.temperatures().to_vec(),
+            r.energies().to_vec(),
+            r.changes().to_vec(),
+            r.sweep_times().to_vec(),
+            r.best_energy(),
+            r.best_labels().to_vec(),
+        );
+        let mut restored = GibbsCheckpoint::new(
+            checkpoint.sweep(),
+            checkpoint.labels().to_vec(),
+            checkpoint.seed(),
+            checkpoint.rng_position(),
+            checkpoint.energy(),
+            report,
+        );
+
+        let mut fresh = model();
+        sampler.resume(&mut fresh, &proposal, &mut restored, usize::MAX, |_, _| {}).unwrap();
+        assert_eq!(fresh.labels(), straight.labels());
+        assert_eq!(restored.report().energies(), full.energies());
+        assert_eq!(restored.report().changes(), full.changes());
+    }
+
+    #[test]
+    fn seeded_run_draws_one_chacha_stream() {
+        let sampler = GibbsSampler::new(25, ConstantAnnealer::new(1.0));
+        let proposal = DiscreteProposal::new(2);
+        let mut mrf = model();
+        sampler.run(&mut mrf, &proposal, RunOptions { seed: Some(13) }).unwrap();
+
+        let mut manual = model();
+        let mut rng = ChaCha12Rng::seed_from_u64(13);
+        for _ in 0..25 {
+            sweep(1.0, &mut manual, &proposal, &mut rng);
+        }
+        assert_eq!(mrf.labels(), manual.labels());
+    }
+
+    #[test]
+    fn zero_thin_fails() {
+        let sampler = GibbsSampler::new(1, ConstantAnnealer::new(1.0));
+        let collect = SampleOptions { thin: 0, ..Default::default() };
+        let result = sampler.sample(&mut model(), &DiscreteProposal::new(2), RunOptions::default(), collect);
+        assert!(matches!(result, Err(MrfError::InvalidParameter { name: "thin", .. })));
+    }
+
+    #[test]
+    fn conditional_sampling_matches_exact() {
+        let mut mrf = model();
+        mrf.clamp(0, 1).unwrap();
+        mrf.clamp(5, 0).unwrap();
+        let exact = ExactInference::new(2, 1 << 10).run(&mrf).unwrap();
+        let sampler = GibbsSampler::new(20_000, ConstantAnnealer::new(1.0));
+        let collect = SampleOptions { burn_in: 100, ..Default::default() };
+        let result = sampler.sample(&mut mrf, &DiscreteProposal::new(2), RunOptions { seed: Some(5) }, collect).unwrap();
+        assert_eq!(result.probability(0, &1), 1.0);
+        assert_eq!(result.probability(5, &0), 1.0);
+        for (node, p) in exact.marginals.iter().enumerate() {
+            assert!((result.probability(node, &1) - p[1]).abs() < 0.02);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use crate::state::Label;
+use crate::MRF;
+use crate::samplers::{Annealer, RunOptions, SingleSiteProposal};
+use crate::error::MrfError;
+use rand::{Rng, RngExt};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rand::seq::SliceRandom;
+
+pub struct MetropolisStats {
+    /// Fraction of proposals accepted in each sweep; clamped nodes are not proposed
+    pub acceptance_rates: Vec<f64>,
+}
+
+/// Single-site Metropolis-Hastings sampler.
+/// Each visit draws one candidate, scores only the current and proposed labels
+/// with `node_energy_with`, and accepts with probability
+/// min(1, exp(-ΔE / T) · q(current | proposed) / q(proposed | current)).
+pub struct MetropolisSampler<A: Annealer> {
+    sweeps: usize,
+    annealer: A,
+}
+
+impl<A: Annealer> MetropolisSampler<A> {
+    pub fn new(sweeps: usize, annealer: A) -> Self {
+        Self { sweeps, annealer }
+    }
+
+    pub fn sweeps(&self) -> usize {
+        self.sweeps
+    }
+
+    pub fn annealer(&self) -> &A {
+        &self.annealer
+    }
+
+    /// One pass over all free nodes in random order; returns the number of accepted moves
+    fn sweep<L: Label>(
+        &self,
+        temperature: f64,
+        mrf: &mut MRF<L>,
+        proposal: &impl SingleSiteProposal<L>,
+        rng: &mut impl Rng,
+    ) -> usize {
+        let mut indices = mrf.free_nodes();
+        indices.shuffle(rng);
+
+        let mut accepted = 0;
+        for &node in &indices {
+            let current = mrf.graph().get_node(node).state().clone();
+            let proposed = proposal.propose(&current, rng);
+            let delta = mrf.node_energy_with(node, &proposed) - mrf.node_energy(node);
+            let log_accept = -delta / temperature + proposal.log_ratio(&current, &proposed);
+            if log_accept >= 0.0 || rng.random::<f64>().ln() < log_accept {
+                mrf.graph_mut().get_node_mut(node).set_state(proposed);
+                accepted += 1;
+            }
+        }
+        accepted
+    }
+
+    pub fn run<L: Label>(
+        &self,
+        mrf: &mut MRF<L>,
+        proposal: &impl SingleSiteProposal<L>,
+        opts: RunOptions,
+    ) -> Result<MetropolisStats, MrfError> {
+        self.run_with(mrf, proposal, opts, |_, _| {})
+    }
+
+    pub fn run_with<L: Label>(
+        &self,
+        mrf: &mut MRF<L>,
+        proposal: &impl SingleSiteProposal<L>,
+        opts: RunOptions,
+        mut on_sweep: impl FnMut(usize, &MRF<L>),
+    ) -> Result<MetropolisStats, MrfError> {
+        let mut rng = match opts.seed {
+            Some(s) => StdRng::seed_from_u64(s),
+            None => StdRng::from_rng(&mut rand::rng()),
+        };
+        let num_free = mrf.free_nodes().len().max(1) as f64;
+        let mut acceptance_rates = Vec::with_capacity(self.sweeps);
+        on_sweep(0, mrf);
+        for i in 0..self.sweeps {
+            let temp = self.annealer.temperature(i);
+            let accepted = self.sweep(temp, mrf, proposal, &mut rng);
+            acceptance_rates.push(accepted as f64 / num_free);
+            on_sweep(i + 1, mrf);
+        }
+        Ok(MetropolisStats { acceptance_rates })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Four, Graph, Grid2D};
+    use crate::inference::ExactInference;
+    use crate::potentials::{CliquePotential, TablePotential, UnaryPotential};
+    use crate::samplers::{ConstantAnnealer, DiscreteProposal, RandomWalkProposal};
+
+    #[test]
+    fn matches_exact_marginals() {
+        let grid: Grid2D<usize> = Grid2D::new(3, 2, Four);
+        let potts = TablePotential::new(vec![0.0, 0.7, 0.7, 0.7, 0.0, 0.7, 0.7, 0.7, 0.0], 3, 2).unwrap();
+        let field = UnaryPotential::new((0..6).map(|i| vec![0.0, 0.2 * i as f64 - 0.5, 0.4]).collect()).unwrap();
+        let mut mrf = MRF::<usize>::builder().grid(grid).potential(potts).potential(field).build().unwrap();
+        let exact = ExactInference::new(3, 1 << 10).run(&mrf).unwrap();
+
+        let sweeps = 30_000;
+        let mut counts = [[0usize; 3]; 6];
+        let sampler = MetropolisSampler::new(sweeps, ConstantAnnealer::new(1.0));
+        let stats = sampler.run_with(&mut mrf, &DiscreteProposal::new(3), RunOptions { seed: Some(2) }, |i, m| {
+            if i > 0 {
+                for (node, c) in counts.iter_mut().enumerate() {
+                    c[*m.graph().get_node(node).state()] += 1;
+                }
+            }
+        }).unwrap();
+        assert_eq!(stats.acceptance_rates.len(), sweeps);
+        for (c, p) in counts.iter().zip(&exact.marginals) {
+            for l in 0..3 {
+                assert!((c[l] as f64 / sweeps as f64 - p[l]).abs() < 0.02);
+            }
+        }
+    }
+
+    /// (x - 2)^2 / 2, a standard normal centred on 2
+    struct Quadratic;
+
+    impl CliquePotential<f64> for Quadratic {
+        fn order(&self) -> usize { 1 }
+        fn score(&self, states: &[f64]) -> f64 {
+            (states[0] - 2.0).powi(2) / 2.0
+        }
+    }
+
+    #[test]
+    fn samples_continuous_labels() {
+        let mut mrf = MRF::<f64>::builder().graph(Graph::new(4)).potential(Quadratic).build().unwrap();
+        let sweeps = 20_000;
+        let (mut sum, mut sum_sq) = (0.0, 0.0);
+        let sampler = MetropolisSampler::new(sweeps, ConstantAnnealer::new(1.0));
+        let stats = sampler.run_with(&mut mrf, &RandomWalkProposal::new(2.5), RunOptions { seed: Some(9) }, |i, m| {
+            if i > 0 {
+                for node in 0..4 {
+                    let x = *m.graph().get_node(node).state();
+                    sum += x;
+                    sum_sq += x * x;
+                }
+            }
+        }).unwrap();
+        let n = (sweeps * 4) as f64;
+        let mean = sum / n;
+        let variance = sum_sq / n - mean * mean;
+        assert!((mean - 2.0).abs() < 0.05);
+        assert!((variance - 1.0).abs() < 0.1);
+        let rate = stats.acceptance_rates.iter().sum::<f64>() / sweeps as f64;
+        assert!(rate > 0.2 && rate < 0.9);
+    }
+}
@@ -1,12 +1,27 @@
 use crate::MRF;
 use crate::error::MrfError;
 use crate::state::Label;
-use rand::Rng;
+use rand::{Rng, RngExt};
 
 mod gibbs;
 mod annealers;
+mod cluster;
+mod swendsen_wang;
+mod wolff;
+mod metropolis;
+mod chromatic;
+mod tempering;
+mod collect;
+mod report;
 pub use self::annealers::{ConstantAnnealer, LinearAnnealer, ExponentialAnnealer, LogarithmicAnnealer};
-pub use self::gibbs::{GibbsSampler, RunOptions};
+pub use self::gibbs::{GibbsCheckpoint, GibbsSampler, RunOptions};
+pub use self::collect::{SampleOptions, SampleResult};
+pub use self::report::RunReport;
+pub use self::swendsen_wang::SwendsenWangSampler;
+pub use self::wolff::{WolffSampler, WolffStats};
+pub use self::metropolis::{MetropolisSampler, MetropolisStats};
+pub use self::chromatic::ChromaticGibbsSampler;
+pub use self::tempering::{ReplicaExchangeReport, ReplicaExchangeSampler};
 
 pub trait Sampler<L: Label> {
     fn run(
@@ -17,7 +32,15 @@ pub trait Sampler<L: Label> {
     ) -> Result<(), MrfError>;
 }
 
+/// Temperature schedule indexed by the caller's unit of progress.
+///
+/// Gibbs, Metropolis, chromatic and Swendsen-Wang samplers advance it once per
+/// sweep over the free nodes, so `LinearAnnealer::new(5.0, 0.1, 0.25)` cools by
+/// 0.1 per sweep. `WolffSampler` advances it once per single-cluster update,
+/// `MeanField` once per iteration, and `ReplicaExchangeSampler::from_annealer` reads
+/// it once per rung of the ladder.
 pub trait Annealer {
+    /// Temperature at step `sweep` of the schedule, counting from 0
     fn temperature(&self, sweep: usize) -> f64;
 }
 
@@ -25,6 +48,19 @@ pub trait Proposal<L: Label> {
     fn candidates(&self, current: &L, rng: &mut impl Rng) -> Vec<L>;
 }
 
+/// Draws a single candidate label per step, so the label space is never enumerated.
+/// Used by `MetropolisSampler`; suitable for huge or continuous label spaces.
+pub trait SingleSiteProposal<L: Label> {
+    fn propose(&self, current: &L, rng: &mut impl Rng) -> L;
+
+    /// ln q(current | proposed) - ln q(proposed | current), the Hastings correction.
+    /// Zero for symmetric proposals.
+    fn log_ratio(&self, current: &L, proposed: &L) -> f64 {
+        let _ = (current, proposed);
+        0.0
+    }
+}
+
 /// Enumerates all discrete labels — use with usize, Label(usize), etc.
 pub struct DiscreteProposal {
     num_labels: usize,
@@ -34,9 +70,40 @@ impl DiscreteProposal {
     pub fn new(num_labels: usize) -> Self {
         Self { num_labels }
     }
+
+    pub fn num_labels(&self) -> usize {
+        self.num_labels
+    }
 }
 impl Proposal<usize> for DiscreteProposal {
     fn candidates(&self, _current: &usize, _rng: &mut impl Rng) -> Vec<usize> {
         (0..self.num_labels).collect()
     }
-}
\ No newline at end of file
+}
+/// Uniform over all labels, which is symmetric
+impl SingleSiteProposal<usize> for DiscreteProposal {
+    fn propose(&self, _current: &usize, rng: &mut impl Rng) -> usize {
+        rng.random_range(0..self.num_labels)
+    }
+}
+
+/// Symmetric random walk for continuous labels: current + U(-width, width)
+pub struct RandomWalkProposal {
+    width: f64,
+}
+
+impl RandomWalkProposal {
+    pub fn new(width: f64) -> Self {
+        Self { width }
+    }
+
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+}
+
+impl SingleSiteProposal<f64> for RandomWalkProposal {
+    fn propose(&self, current: &f64, rng: &mut impl Rng) -> f64 {
+        current + rng.random_range(-self.width..self.width)
+    }
+}
@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+/// Per-sweep trace of a sampling run
+#[derive(Clone)]
+pub struct RunReport<L> {
+    initial_energy: f64,
+    temperatures: Vec<f64>,
+    energies: Vec<f64>,
+    changes: Vec<usize>,
+    sweep_times: Vec<Duration>,
+    best_energy: f64,
+    best_labels: Vec<L>,
+}
+
+impl<L: Clone> RunReport<L> {
+    pub(crate) fn new(labels: Vec<L>, energy: f64, sweeps: usize) -> Self {
+        Self {
+            initial_energy: energy,
+            temperatures: Vec::with_capacity(sweeps),
+            energies: Vec::with_capacity(sweeps),
+            changes: Vec::with_capacity(sweeps),
+            sweep_times: Vec::with_capacity(sweeps),
+            best_energy: energy,
+            best_labels: labels,
+        }
+    }
+
+    /// Rebuilds a report from values read back from its getters,
+    /// e.g. when restoring a saved `GibbsCheckpoint`
+    pub fn from_parts(
+        initial_energy: f64,
+        temperatures: Vec<f64>,
+        energies: Vec<f64>,
+        changes: Vec<usize>,
+        sweep_times: Vec<Duration>,
+        best_energy: f64,
+        best_labels: Vec<L>,
+    ) -> Self {
+        Self { initial_energy, temperatures, energies, changes, sweep_times, best_energy, best_labels }
+    }
+
+    /// Records one finished sweep; `labels` is only called when the energy improves
+    pub(crate) fn push(
+        &mut self,
+        temperature: f64,
+        energy: f64,
+        changes: usize,
+        time: Duration,
+        labels: impl FnOnce() -> Vec<L>,
+    ) {
+        self.temperatures.push(temperature);
+        self.energies.push(energy);
+        self.changes.push(changes);
+        self.sweep_times.push(time);
+        if energy < self.best_energy {
+            self.best_energy = energy;
+            self.best_labels = labels();
+        }
+    }
+
+    pub fn num_sweeps(&self) -> usize {
+        self.energies.len()
+    }
+
+    /// Total energy before the first sweep
+    pub fn initial_energy(&self) -> f64 {
+        self.initial_energy
+    }
+
+    /// Annealer temperature used for each sweep
+    pub fn temperatures(&self) -> &[f64] {
+        &self.temperatures
+    }
+
+    /// Total energy after each sweep
+    pub fn energies(&self) -> &[f64] {
+        &self.energies
+    }
+
+    /// Number of nodes whose label changed in each sweep
+    pub fn changes(&self) -> &[usize] {
+        &self.changes
+    }
+
+    /// Wall time of each sweep
+    pub fn sweep_times(&self) -> &[Duration] {
+        &self.sweep_times
+    }
+
+    pub fn total_time(&self) -> Duration {
+        self.sweep_times.iter().sum()
+    }
+
+    /// Lowest energy seen, including the initial labelling
+    pub fn best_energy(&self) -> f64 {
+        self.best_energy
+    }
+
+    pub fn best_labels(&self) -> &[L] {
+        &self.best_labels
+    }
+
+    pub(crate) fn into_best(self) -> (Vec<L>, f64) {
+        (self.best_labels, self.best_energy)
+    }
+}
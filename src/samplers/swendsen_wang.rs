@@ -0,0 +1,192 @@
+use crate::MRF;
+use crate::samplers::{Annealer, DiscreteProposal, RunOptions};
+use crate::samplers::cluster::{DisjointSets, PottsModel, sample_log_weights};
+use crate::error::MrfError;
+use rand::{Rng, RngExt};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// Swendsen-Wang cluster sampler for Potts/Ising-form models.
+///
+/// Every pairwise potential must score equal labels c and unequal labels c + J
+/// with J >= 0; unary potentials act as an external field. Each sweep bonds
+/// equally-labelled neighbours with probability 1 - exp(-J / T), then relabels
+/// every bonded cluster at once from the field's conditional distribution.
+/// Clusters containing a clamped node keep their label.
+pub struct SwendsenWangSampler<A: Annealer> {
+    sweeps: usize,
+    annealer: A,
+}
+
+impl<A: Annealer> SwendsenWangSampler<A> {
+    pub fn new(sweeps: usize, annealer: A) -> Self {
+        Self { sweeps, annealer }
+    }
+
+    pub fn sweeps(&self) -> usize {
+        self.sweeps
+    }
+
+    pub fn annealer(&self) -> &A {
+        &self.annealer
+    }
+
+    fn sweep(
+        &self,
+        temperature: f64,
+        mrf: &mut MRF<usize>,
+        model: &PottsModel,
+        rng: &mut impl Rng,
+    ) {
+        let n = mrf.num_nodes();
+        let labels: Vec<usize> = (0..n).map(|i| *mrf.graph().get_node(i).state()).collect();
+
+        let mut sets = DisjointSets::new(n);
+        for &(i, j, coupling) in &model.bonds {
+            if labels[i] == labels[j] && rng.random::<f64>() < 1.0 - (-coupling / temperature).exp() {
+                sets.union(i, j);
+            }
+        }
+
+        let mut cluster_of = vec![usize::MAX; n];
+        let mut clusters: Vec<Vec<usize>> = Vec::new();
+        for i in 0..n {
+            let root = sets.find(i);
+            if cluster_of[root] == usize::MAX {
+                cluster_of[root] = clusters.len();
+                clusters.push(Vec::new());
+            }
+            clusters[cluster_of[root]].push(i);
+        }
+
+        for cluster in &clusters {
+            // A cluster holding evidence keeps the clamped label; bonds only join equal labels
+            if cluster.iter().any(|&i| mrf.is_clamped(i)) {
+                continue;
+            }
+            let log_weights: Vec<f64> = (0..model.num_labels)
+                .map(|l| -model.cluster_field(cluster, l) / temperature)
+                .collect();
+            let label = sample_log_weights(&log_weights, rng);
+            for &i in cluster {
+                mrf.graph_mut().get_node_mut(i).set_state(label);
+            }
+        }
+    }
+
+    pub fn run(
+        &self,
+        mrf: &mut MRF<usize>,
+        proposal: &DiscreteProposal,
+        opts: RunOptions,
+    ) -> Result<(), MrfError> {
+        self.run_with(mrf, proposal, opts, |_, _| {})
+    }
+
+    pub fn run_with(
+        &self,
+        mrf: &mut MRF<usize>,
+        proposal: &DiscreteProposal,
+        opts: RunOptions,
+        mut on_sweep: impl FnMut(usize, &MRF<usize>),
+    ) -> Result<(), MrfError> {
+        let model = PottsModel::new(mrf, proposal.num_labels())?;
+        let mut rng = match opts.seed {
+            Some(s) => StdRng::seed_from_u64(s),
+            None => StdRng::from_rng(&mut rand::rng()),
+        };
+        on_sweep(0, mrf);
+        for i in 0..self.sweeps {
+            let temp = self.annealer.temperature(i);
+            self.sweep(temp, mrf, &model, &mut rng);
+            on_sweep(i + 1, mrf);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Four, Grid2D};
+    use crate::inference::ExactInference;
+    use crate::potentials::{TablePotential, UnaryPotential};
+    use crate::samplers::{ConstantAnnealer, LinearAnnealer};
+
+    fn potts(coupling: f64, num_labels: usize) -> TablePotential {
+        let scores = (0..num_labels * num_labels)
+            .map(|k| if k / num_labels == k % num_labels { -0.2 } else { coupling - 0.2 })
+            .collect();
+        TablePotential::new(scores, num_labels, 2).unwrap()
+    }
+
+    #[test]
+    fn matches_exact_marginals() {
+        let grid: Grid2D<usize> = Grid2D::new(3, 2, Four);
+        let field = UnaryPotential::new((0..6).map(|i| vec![0.0, 0.25 * i as f64 - 0.6, 0.3]).collect()).unwrap();
+        let mut mrf = MRF::<usize>::builder().grid(grid).potential(potts(0.9, 3)).potential(field).build().unwrap();
+        let exact = ExactInference::new(3, 1 << 10).run(&mrf).unwrap();
+
+        let sweeps = 20_000;
+        let mut counts = [[0usize; 3]; 6];
+        let sampler = SwendsenWangSampler::new(sweeps, ConstantAnnealer::new(1.0));
+        sampler.run_with(&mut mrf, &DiscreteProposal::new(3), RunOptions { seed: Some(11) }, |i, m| {
+            if i > 0 {
+                for (node, c) in counts.iter_mut().enumerate() {
+                    c[*m.graph().get_node(node).state()] += 1;
+                }
+            }
+        }).unwrap();
+        for (c, p) in counts.iter().zip(&exact.marginals) {
+            for l in 0..3 {
+                assert!((c[l] as f64 / sweeps as f64 - p[l]).abs() < 0.02);
+            }
+        }
+    }
+
+    #[test]
+    fn cold_run_orders_the_lattice() {
+        let grid: Grid2D<usize> = Grid2D::new(8, 8, Four);
+        let mut mrf = MRF::<usize>::builder().grid(grid).potential(potts(1.0, 2)).build().unwrap();
+        let sampler = SwendsenWangSampler::new(30, LinearAnnealer::new(2.0, 0.1, 0.1));
+        sampler.run(&mut mrf, &DiscreteProposal::new(2), RunOptions { seed: Some(3) }).unwrap();
+        let first = *mrf.graph().get_node(0).state();
+        assert!((0..64).all(|i| *mrf.graph().get_node(i).state() == first));
+    }
+
+    #[test]
+    fn rejects_non_potts_tables() {
+        let grid: Grid2D<usize> = Grid2D::new(2, 2, Four);
+        let linear = TablePotential::new(vec![0.0, 1.0, 2.0, 1.0, 0.0, 1.0, 2.0, 1.0, 0.0], 3, 2).unwrap();
+        let mut mrf = MRF::<usize>::builder().grid(grid).potential(linear).build().unwrap();
+        let sampler = SwendsenWangSampler::new(1, ConstantAnnealer::new(1.0));
+        let result = sampler.run(&mut mrf, &DiscreteProposal::new(3), RunOptions::default());
+        assert!(matches!(result, Err(MrfError::NotPotts { potential: 0 })));
+    }
+
+    #[test]
+    fn matches_conditional_marginals() {
+        let grid: Grid2D<usize> = Grid2D::new(3, 2, Four);
+        let field = UnaryPotential::new((0..6).map(|i| vec![0.0, 0.25 * i as f64 - 0.6, 0.3]).collect()).unwrap();
+        let mut mrf = MRF::<usize>::builder().grid(grid).potential(potts(0.9, 3)).potential(field).build().unwrap();
+        mrf.clamp(1, 2).unwrap();
+        let exact = ExactInference::new(3, 1 << 10).run(&mrf).unwrap();
+
+        let sweeps = 20_000;
+        let mut counts = [[0usize; 3]; 6];
+        let sampler = SwendsenWangSampler::new(sweeps, ConstantAnnealer::new(1.0));
+        sampler.run_with(&mut mrf, &DiscreteProposal::new(3), RunOptions { seed: Some(12) }, |i, m| {
+            if i > 0 {
+                for (node, c) in counts.iter_mut().enumerate() {
+                    c[*m.graph().get_node(node).state()] += 1;
+                }
+            }
+        }).unwrap();
+        assert_eq!(counts[1][2], sweeps);
+        for (c, p) in counts.iter().zip(&exact.marginals) {
+            for l in 0..3 {
+                assert!((c[l] as f64 / sweeps as f64 - p[l]).abs() < 0.02);
+            }
+        }
+    }
+}
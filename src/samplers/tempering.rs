@@ -0,0 +1,192 @@
+use crate::state::Label;
+use crate::MRF;
+use crate::samplers::{Annealer, Proposal, RunOptions, SampleOptions, SampleResult};
+use crate::samplers::gibbs::sweep;
+use crate::error::MrfError;
+use rand::RngExt;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+pub struct ReplicaExchangeReport<L> {
+    /// Fraction of accepted swaps between rungs k and k + 1
+    pub swap_acceptance: Vec<f64>,
+    /// Kept sweeps of the T = 1 chain; its MAP is the lowest-energy labelling that chain visited
+    pub samples: SampleResult<L>,
+}
+
+/// Parallel tempering: Gibbs chains at a ladder of temperatures, with periodic
+/// swaps between adjacent rungs accepted with probability
+/// min(1, exp((1/T_k - 1/T_{k+1}) (E_k - E_{k+1}))).
+///
+/// The ladder must contain T = 1, the chain whose samples are returned and whose
+/// final labelling is left in the MRF. Every chain starts from the MRF's labelling.
+/// Replica energies are kept up to date from each sweep's energy change, so the
+/// total energy is only computed once.
+pub struct ReplicaExchangeSampler {
+    sweeps: usize,
+    temperatures: Vec<f64>,
+    swap_interval: usize,
+}
+
+impl ReplicaExchangeSampler {
+    /// Proposes swaps every `swap_interval` sweeps
+    pub fn new(sweeps: usize, temperatures: Vec<f64>, swap_interval: usize) -> Self {
+        Self { sweeps, temperatures, swap_interval }
+    }
+
+    /// Ladder of `replicas` rungs at `annealer.temperature(0..replicas)`
+    pub fn from_annealer(sweeps: usize, annealer: &impl Annealer, replicas: usize, swap_interval: usize) -> Self {
+        Self::new(sweeps, (0..replicas).map(|k| annealer.temperature(k)).collect(), swap_interval)
+    }
+
+    pub fn sweeps(&self) -> usize {
+        self.sweeps
+    }
+
+    pub fn temperatures(&self) -> &[f64] {
+        &self.temperatures
+    }
+
+    pub fn swap_interval(&self) -> usize {
+        self.swap_interval
+    }
+
+    pub fn run<L: Label>(
+        &self,
+        mrf: &mut MRF<L>,
+        proposal: &impl Proposal<L>,
+        opts: RunOptions,
+        collect: SampleOptions,
+    ) -> Result<ReplicaExchangeReport<L>, MrfError> {
+        if let Some(&t) = self.temperatures.iter().find(|&&t| t <= 0.0 || t.is_nan()) {
+            return Err(MrfError::InvalidParameter { name: "temperature", value: t });
+        }
+        let target = self.temperatures.iter().position(|&t| (t - 1.0).abs() < 1e-12)
+            .ok_or(MrfError::InvalidParameter {
+                name: "temperatures",
+                value: self.temperatures.first().copied().unwrap_or(f64::NAN),
+            })?;
+        if self.swap_interval == 0 {
+            return Err(MrfError::InvalidParameter { name: "swap_interval", value: 0.0 });
+        }
+        if collect.thin == 0 {
+            return Err(MrfError::InvalidParameter { name: "thin", value: 0.0 });
+        }
+        let mut rng = match opts.seed {
+            Some(s) => StdRng::seed_from_u64(s),
+            None => StdRng::from_rng(&mut rand::rng()),
+        };
+
+        let rungs = self.temperatures.len();
+        // replicas[k] is the labelling currently at temperature k
+        let mut replicas: Vec<Vec<L>> = vec![mrf.labels(); rungs];
+        let mut energies = vec![mrf.energy(); rungs];
+        let mut attempts = vec![0usize; rungs.saturating_sub(1)];
+        let mut accepts = vec![0usize; rungs.saturating_sub(1)];
+        let mut samples = SampleResult::new(mrf.num_nodes(), collect.keep_samples);
+        let mut best = (replicas[target].clone(), energies[target]);
+
+        for i in 0..self.sweeps {
+            for (k, &t) in self.temperatures.iter().enumerate() {
+                mrf.set_labels(&replicas[k])?;
+                let (changes, delta) = sweep(t, mrf, proposal, &mut rng);
+                if changes > 0 {
+                    replicas[k] = mrf.labels();
+                }
+                energies[k] += delta;
+            }
+
+            if (i + 1) % self.swap_interval == 0 {
+                // Alternate between even and odd pairs
+                let start = (i / self.swap_interval) % 2;
+                for k in (start..rungs.saturating_sub(1)).step_by(2) {
+                    attempts[k] += 1;
+                    let (beta_k, beta_next) = (1.0 / self.temperatures[k], 1.0 / self.temperatures[k + 1]);
+                    let log_accept = (beta_k - beta_next) * (energies[k] - energies[k + 1]);
+                    if log_accept >= 0.0 || rng.random::<f64>().ln() < log_accept {
+                        replicas.swap(k, k + 1);
+                        energies.swap(k, k + 1);
+                        accepts[k] += 1;
+                    }
+                }
+            }
+            if energies[target] < best.1 {
+                best = (replicas[target].clone(), energies[target]);
+            }
+            if collect.keeps(i + 1) {
+                samples.record(&replicas[target]);
+            }
+        }
+
+        samples.set_map(best.0, best.1);
+        mrf.set_labels(&replicas[target])?;
+        let swap_acceptance = accepts.iter().zip(&attempts)
+            .map(|(&a, &n)| if n == 0 { 0.0 } else { a as f64 / n as f64 })
+            .collect();
+        Ok(ReplicaExchangeReport { swap_acceptance, samples })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Four, Grid2D};
+    use crate::inference::ExactInference;
+    use crate::potentials::{TablePotential, UnaryPotential};
+    use crate::samplers::{DiscreteProposal, ExponentialAnnealer};
+
+    fn ising(width: usize, height: usize, coupling: f64) -> MRF<usize> {
+        let grid: Grid2D<usize> = Grid2D::new(width, height, Four);
+        let table = TablePotential::new(vec![0.0, coupling, coupling, 0.0], 2, 2).unwrap();
+        MRF::<usize>::builder().grid(grid).potential(table).build().unwrap()
+    }
+
+    #[test]
+    fn visits_both_modes() {
+        let mut mrf = ising(5, 5, 1.5);
+        let sampler = ReplicaExchangeSampler::new(3_000, vec![1.0, 1.6, 2.6, 4.0], 1);
+        let collect = SampleOptions { keep_samples: true, ..Default::default() };
+        let report = sampler.run(&mut mrf, &DiscreteProposal::new(2), RunOptions { seed: Some(4) }, collect).unwrap();
+
+        let samples = report.samples.samples().unwrap();
+        assert_eq!(samples.len(), 3_000);
+        assert_eq!(report.swap_acceptance.len(), 3);
+        assert!(report.swap_acceptance.iter().all(|&a| a > 0.05 && a <= 1.0));
+        let up = samples.iter().filter(|s| s.iter().sum::<usize>() > 20).count();
+        let down = samples.iter().filter(|s| s.iter().sum::<usize>() < 5).count();
+        assert!(up > 300 && down > 300, "up {up}, down {down}");
+        assert_eq!(mrf.labels(), *samples.last().unwrap());
+    }
+
+    #[test]
+    fn target_chain_matches_exact_marginals() {
+        let grid: Grid2D<usize> = Grid2D::new(3, 2, Four);
+        let table = TablePotential::new(vec![0.0, 1.0, 1.0, 0.0], 2, 2).unwrap();
+        let field = UnaryPotential::new((0..6).map(|i| vec![0.0, 0.2 * i as f64 - 0.5]).collect()).unwrap();
+        let mut mrf = MRF::<usize>::builder().grid(grid).potential(table).potential(field).build().unwrap();
+        let exact = ExactInference::new(2, 1 << 10).run(&mrf).unwrap();
+
+        let sampler = ReplicaExchangeSampler::from_annealer(10_000, &ExponentialAnnealer::new(1.0, 1.5, 0.0), 3, 2);
+        assert_eq!(sampler.temperatures(), &[1.0, 1.5, 2.25]);
+        let collect = SampleOptions { burn_in: 200, thin: 2, keep_samples: false };
+        let report = sampler.run(&mut mrf, &DiscreteProposal::new(2), RunOptions { seed: Some(6) }, collect).unwrap();
+        assert_eq!(report.samples.num_samples(), 4_900);
+        assert!(report.samples.samples().is_none());
+        for (node, p) in exact.marginals.iter().enumerate() {
+            assert!((report.samples.probability(node, &1) - p[1]).abs() < 0.03);
+        }
+
+        // The tracked energies agree with a full recomputation
+        assert!((report.samples.map_energy() - exact.map_energy).abs() < 1e-9);
+        mrf.set_labels(report.samples.map_labels()).unwrap();
+        assert!((report.samples.map_energy() - mrf.energy()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ladder_must_contain_unit_temperature() {
+        let mut mrf = ising(2, 2, 1.0);
+        let sampler = ReplicaExchangeSampler::new(1, vec![1.5, 2.0], 1);
+        let result = sampler.run(&mut mrf, &DiscreteProposal::new(2), RunOptions::default(), SampleOptions::default());
+        assert!(matches!(result, Err(MrfError::InvalidParameter { name: "temperatures", .. })));
+    }
+}
@@ -0,0 +1,236 @@
+use crate::MRF;
+use crate::samplers::{Annealer, DiscreteProposal, RunOptions};
+use crate::samplers::cluster::PottsModel;
+use crate::error::MrfError;
+use rand::{Rng, RngExt};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+pub struct WolffStats {
+    /// Number of cluster updates attempted
+    pub steps: usize,
+    /// Mean number of nodes per grown cluster
+    pub mean_cluster_size: f64,
+    /// Fraction of cluster flips accepted; below 1 only when a field is present
+    pub acceptance_rate: f64,
+}
+
+/// Wolff single-cluster sampler for Potts/Ising-form models.
+///
+/// Each step picks a random seed node and a new label, grows a cluster over
+/// equally-labelled interacting neighbours with bond probability 1 - exp(-J / T),
+/// and flips the whole cluster. Unary potentials are handled by a Metropolis
+/// test on the cluster's field energy, and flips of clusters that reach a clamped
+/// node are rejected. Pairwise potentials must have Potts form, as for `SwendsenWangSampler`.
+/// Step i runs at `annealer.temperature(i)`: the schedule advances per cluster
+/// update, not per sweep.
+pub struct WolffSampler<A: Annealer> {
+    steps: usize,
+    annealer: A,
+}
+
+impl<A: Annealer> WolffSampler<A> {
+    pub fn new(steps: usize, annealer: A) -> Self {
+        Self { steps, annealer }
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn annealer(&self) -> &A {
+        &self.annealer
+    }
+
+    /// Grows and possibly flips one cluster. Returns (cluster size, accepted).
+    fn step(
+        &self,
+        temperature: f64,
+        mrf: &mut MRF<usize>,
+        model: &PottsModel,
+        in_cluster: &mut [bool],
+        rng: &mut impl Rng,
+    ) -> (usize, bool) {
+        let seed = rng.random_range(0..mrf.num_nodes());
+        let old = *mrf.graph().get_node(seed).state();
+        if model.num_labels < 2 {
+            return (1, false);
+        }
+        let mut new = rng.random_range(0..model.num_labels - 1);
+        if new >= old {
+            new += 1;
+        }
+
+        let mut cluster = vec![seed];
+        in_cluster[seed] = true;
+        let mut frontier = 0;
+        while frontier < cluster.len() {
+            let i = cluster[frontier];
+            frontier += 1;
+            for &(j, coupling) in &model.adjacency[i] {
+                if !in_cluster[j]
+                    && *mrf.graph().get_node(j).state() == old
+                    && rng.random::<f64>() < 1.0 - (-coupling / temperature).exp()
+                {
+                    in_cluster[j] = true;
+                    cluster.push(j);
+                }
+            }
+        }
+        for &i in &cluster {
+            in_cluster[i] = false;
+        }
+
+        // Flips that would relabel a clamped node leave the constrained space
+        if cluster.iter().any(|&i| mrf.is_clamped(i)) {
+            return (cluster.len(), false);
+        }
+        let delta = model.cluster_field(&cluster, new) - model.cluster_field(&cluster, old);
+        let accepted = delta <= 0.0 || rng.random::<f64>() < (-delta / temperature).exp();
+        if accepted {
+            for &i in &cluster {
+                mrf.graph_mut().get_node_mut(i).set_state(new);
+            }
+        }
+        (cluster.len(), accepted)
+    }
+
+    pub fn run(
+        &self,
+        mrf: &mut MRF<usize>,
+        proposal: &DiscreteProposal,
+        opts: RunOptions,
+    ) -> Result<WolffStats, MrfError> {
+        self.run_with(mrf, proposal, opts, |_, _| {})
+    }
+
+    /// Like `run`, calling `on_step` with the step count before the first step and
+    /// after each one. With no free nodes there is nothing to flip, so no steps are
+    /// taken and the returned stats are all zero.
+    pub fn run_with(
+        &self,
+        mrf: &mut MRF<usize>,
+        proposal: &DiscreteProposal,
+        opts: RunOptions,
+        mut on_step: impl FnMut(usize, &MRF<usize>),
+    ) -> Result<WolffStats, MrfError> {
+        let model = PottsModel::new(mrf, proposal.num_labels())?;
+        let mut rng = match opts.seed {
+            Some(s) => StdRng::seed_from_u64(s),
+            None => StdRng::from_rng(&mut rand::rng()),
+        };
+        let mut in_cluster = vec![false; mrf.num_nodes()];
+        let (mut total_size, mut accepted) = (0, 0);
+        on_step(0, mrf);
+        if mrf.free_nodes().is_empty() {
+            return Ok(WolffStats { steps: 0, mean_cluster_size: 0.0, acceptance_rate: 0.0 });
+        }
+        for i in 0..self.steps {
+            let temp = self.annealer.temperature(i);
+            let (size, ok) = self.step(temp, mrf, &model, &mut in_cluster, &mut rng);
+            total_size += size;
+            accepted += usize::from(ok);
+            on_step(i + 1, mrf);
+        }
+        let steps = self.steps.max(1) as f64;
+        Ok(WolffStats {
+            steps: self.steps,
+            mean_cluster_size: total_size as f64 / steps,
+            acceptance_rate: accepted as f64 / steps,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Four, Graph, Grid2D};
+    use crate::inference::ExactInference;
+    use crate::potentials::{TablePotential, UnaryPotential};
+    use crate::samplers::ConstantAnnealer;
+
+    fn ising(coupling: f64) -> TablePotential {
+        TablePotential::new(vec![0.0, coupling, coupling, 0.0], 2, 2).unwrap()
+    }
+
+    #[test]
+    fn matches_exact_marginals_with_field() {
+        let grid: Grid2D<usize> = Grid2D::new(3, 2, Four);
+        let field = UnaryPotential::new((0..6).map(|i| vec![0.0, 0.3 * i as f64 - 0.7]).collect()).unwrap();
+        let mut mrf = MRF::<usize>::builder().grid(grid).potential(ising(0.8)).potential(field).build().unwrap();
+        let exact = ExactInference::new(2, 1 << 10).run(&mrf).unwrap();
+
+        let steps = 40_000;
+        let mut ones = [0usize; 6];
+        let sampler = WolffSampler::new(steps, ConstantAnnealer::new(1.0));
+        sampler.run_with(&mut mrf, &DiscreteProposal::new(2), RunOptions { seed: Some(5) }, |i, m| {
+            if i > 0 {
+                for (node, c) in ones.iter_mut().enumerate() {
+                    *c += *m.graph().get_node(node).state();
+                }
+            }
+        }).unwrap();
+        for (c, p) in ones.iter().zip(&exact.marginals) {
+            assert!((*c as f64 / steps as f64 - p[1]).abs() < 0.02);
+        }
+    }
+
+    #[test]
+    fn cluster_size_grows_with_coupling() {
+        let proposal = DiscreteProposal::new(2);
+        let run = |coupling: f64| {
+            let grid: Grid2D<usize> = Grid2D::new(16, 16, Four);
+            let mut mrf = MRF::<usize>::builder().grid(grid).potential(ising(coupling)).build().unwrap();
+            WolffSampler::new(2_000, ConstantAnnealer::new(1.0))
+                .run(&mut mrf, &proposal, RunOptions { seed: Some(1) })
+                .unwrap()
+        };
+        let hot = run(0.2);
+        let cold = run(2.0);
+        assert_eq!(hot.steps, 2_000);
+        assert!((hot.acceptance_rate - 1.0).abs() < 1e-12);
+        assert!(hot.mean_cluster_size < 3.0);
+        assert!(cold.mean_cluster_size > 100.0);
+    }
+
+    #[test]
+    fn matches_conditional_marginals() {
+        let grid: Grid2D<usize> = Grid2D::new(3, 2, Four);
+        let field = UnaryPotential::new((0..6).map(|i| vec![0.0, 0.3 * i as f64 - 0.7]).collect()).unwrap();
+        let mut mrf = MRF::<usize>::builder().grid(grid).potential(ising(0.8)).potential(field).build().unwrap();
+        mrf.clamp(4, 0).unwrap();
+        let exact = ExactInference::new(2, 1 << 10).run(&mrf).unwrap();
+
+        let steps = 40_000;
+        let mut ones = [0usize; 6];
+        let sampler = WolffSampler::new(steps, ConstantAnnealer::new(1.0));
+        sampler.run_with(&mut mrf, &DiscreteProposal::new(2), RunOptions { seed: Some(6) }, |i, m| {
+            if i > 0 {
+                for (node, c) in ones.iter_mut().enumerate() {
+                    *c += *m.graph().get_node(node).state();
+                }
+            }
+        }).unwrap();
+        assert_eq!(ones[4], 0);
+        for (c, p) in ones.iter().zip(&exact.marginals) {
+            assert!((*c as f64 / steps as f64 - p[1]).abs() < 0.02);
+        }
+    }
+
+    #[test]
+    fn no_free_nodes_takes_no_steps() {
+        let proposal = DiscreteProposal::new(2);
+        let sampler = WolffSampler::new(10, ConstantAnnealer::new(1.0));
+        let mut empty = MRF::<usize>::builder().graph(Graph::new(0)).potential(ising(1.0)).build().unwrap();
+        let stats = sampler.run(&mut empty, &proposal, RunOptions { seed: Some(2) }).unwrap();
+        assert_eq!(stats.steps, 0);
+
+        let grid: Grid2D<usize> = Grid2D::new(2, 1, Four);
+        let mut mrf = MRF::<usize>::builder().grid(grid).potential(ising(1.0)).build().unwrap();
+        mrf.clamp(0, 1).unwrap();
+        mrf.clamp(1, 0).unwrap();
+        let stats = sampler.run(&mut mrf, &proposal, RunOptions { seed: Some(2) }).unwrap();
+        assert_eq!(stats.steps, 0);
+        assert_eq!(mrf.labels(), vec![1, 0]);
+    }
+}